@@ -0,0 +1,77 @@
+//! Framebuffer fill speed of the fast paths against drawing pixel by pixel, run on the
+//! host with `cargo bench --target x86_64-unknown-linux-gnu --features std --bench fill`
+
+use std::time::Instant;
+
+use embedded_graphics_core::{pixelcolor::Gray4, prelude::*, primitives::Rectangle};
+
+use ssd1320::{
+    config::Config, emulator::Ssd1320z2Emulator, rotation::DisplayRotation,
+    size::DisplaySize320x132, Ssd1320z2,
+};
+
+const ITERATIONS: u32 = 200;
+
+/// Print the mean time of a drawing operation
+fn bench<F>(name: &str, mut operation: F)
+where
+    F: FnMut(),
+{
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        operation();
+    }
+    let mean = start.elapsed() / ITERATIONS;
+    println!("{:<40} {:>10.1} us", name, mean.as_secs_f64() * 1e6);
+}
+
+fn main() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display = Ssd1320z2::new(
+        emulator.interface(),
+        emulator.cs1(),
+        emulator.cs2(),
+        DisplaySize320x132,
+        DisplayRotation::Rotate0,
+    )
+    .init(&Config::default())
+    .unwrap()
+    .into_buffered_graphics_mode();
+
+    let screen = display.bounding_box();
+    let area = Rectangle::new(Point::new(3, 5), Size::new(201, 97));
+    let color = Gray4::new(7);
+    let image: Vec<Gray4> = area
+        .points()
+        .map(|point| Gray4::new(((point.x + point.y) % 16) as u8))
+        .collect();
+
+    bench("clear", || display.clear(color).unwrap());
+    bench("clear pixel by pixel", || {
+        display
+            .draw_iter(screen.points().map(|point| Pixel(point, color)))
+            .unwrap()
+    });
+    bench("fill_solid odd edges", || {
+        display.fill_solid(&area, color).unwrap()
+    });
+    bench("fill_solid odd edges pixel by pixel", || {
+        display
+            .draw_iter(area.points().map(|point| Pixel(point, color)))
+            .unwrap()
+    });
+    bench("fill_contiguous odd edges", || {
+        display
+            .fill_contiguous(&area, image.iter().copied())
+            .unwrap()
+    });
+    bench("fill_contiguous odd edges pixel by pixel", || {
+        display
+            .draw_iter(
+                area.points()
+                    .zip(image.iter().copied())
+                    .map(|(point, color)| Pixel(point, color)),
+            )
+            .unwrap()
+    });
+}
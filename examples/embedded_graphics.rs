@@ -24,7 +24,13 @@ use embedded_graphics::{
 use tinybmp::Bmp;
 
 use display_interface_spi::SPIInterfaceNoCS;
-use ssd1320::buffered_graphics::BufferedSsd1320z2;
+use ssd1320::{
+    config::Config,
+    dither::{Dithered, Dithering},
+    rotation::DisplayRotation,
+    size::DisplaySize320x132,
+    Ssd1320z2,
+};
 
 #[entry]
 fn main() -> ! {
@@ -61,9 +67,27 @@ fn main() -> ! {
 
     let iface = SPIInterfaceNoCS::new(spi, dc);
 
-    let mut display = BufferedSsd1320z2::new(iface, cs1, cs2);
-
-    display.init(&mut res, &mut delay);
+    let mut display = Ssd1320z2::new(
+        iface,
+        cs1,
+        cs2,
+        DisplaySize320x132,
+        DisplayRotation::Rotate0,
+    );
+
+    display.reset(&mut res, &mut delay).unwrap();
+
+    // Keep the 21 KB framebuffer out of the stack, a zeroed static is placed in .bss
+    // without being built on the stack first
+    static mut BUFFER: [u8; 320 * 132 / 2] = [0; 320 * 132 / 2];
+    // SAFETY: `main` never returns and this is the only reference to `BUFFER`
+    let buffer: &'static mut [u8] = unsafe { &mut *core::ptr::addr_of_mut!(BUFFER) };
+    let mut display = display
+        .init(&Config::default())
+        .unwrap()
+        .into_buffered_graphics_mode_with_buffer(buffer)
+        .map_err(|(error, _)| error)
+        .unwrap();
 
     let bmp: Bmp<Rgb565, 'static> = Bmp::from_slice(include_bytes!("rust.bmp")).unwrap();
 
@@ -105,8 +129,9 @@ fn main() -> ! {
         .unwrap();
 
     // rust image
+    // Quantize the RGB image to the 16 gray levels with error diffusion
     Image::new(&bmp, Point::new(140, 0))
-        .draw(&mut display.color_converted())
+        .draw(&mut Dithered::new(&mut display, Dithering::FloydSteinberg))
         .unwrap();
 
     display.flush().unwrap();
@@ -15,7 +15,7 @@ use stm32f4xx_hal::{
 };
 
 use display_interface_spi::SPIInterfaceNoCS;
-use ssd1320::Ssd1320z2;
+use ssd1320::{config::Config, rotation::DisplayRotation, size::DisplaySize320x132, Ssd1320z2};
 
 #[entry]
 fn main() -> ! {
@@ -52,19 +52,31 @@ fn main() -> ! {
 
     let iface = SPIInterfaceNoCS::new(spi, dc);
 
-    let mut display = Ssd1320z2::new(iface, cs1, cs2);
+    let mut display = Ssd1320z2::new(
+        iface,
+        cs1,
+        cs2,
+        DisplaySize320x132,
+        DisplayRotation::Rotate0,
+    );
 
-    display.reset(&mut res, &mut delay);
+    display.reset(&mut res, &mut delay).unwrap();
 
-    display.init();
+    let mut display = display.init(&Config::default()).unwrap();
 
-    display.set_draw_area((190, 78), (190 + 83, 78 + 23));
+    display
+        .set_draw_area((190, 78), (190 + 83, 78 + 23))
+        .unwrap();
     for _ in 0..(54 * 44) {
-        display.draw(&[0x00]);
+        display.draw(&[0x00]).unwrap();
     }
-    display.set_draw_area((192, 80), (192 + 79, 80 + 19));
+    display
+        .set_draw_area((192, 80), (192 + 79, 80 + 19))
+        .unwrap();
     for i in 0..(50 * 40) {
-        display.draw(&[(i % 4) as u8 | (((i >> 2) % 4) << 2) as u8]);
+        display
+            .draw(&[(i % 4) as u8 | (((i >> 2) % 4) << 2) as u8])
+            .unwrap();
     }
 
     let mut select_figure = 0;
@@ -85,7 +97,7 @@ fn main() -> ! {
                 ((180 + 10, 10), (180 + 41, 41))
             }
         };
-        display.set_draw_area(begin, end);
+        display.set_draw_area(begin, end).unwrap();
         for _ in 0..((end.1 - begin.1 + 1) / 16) {
             for _ in 0..((end.0 - begin.0 + 1) / 2) {
                 display
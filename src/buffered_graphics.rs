@@ -1,93 +1,901 @@
 //! Buffered graphics mode.
 
-use crate::{consts::ssd1320z2, error::Error, Ssd1320z2};
+use crate::{
+    config::Config,
+    controllers::{Controllers, Frame},
+    error::Error,
+    mode::{InitializedMode, RawMode},
+    size::{DisplaySize, DisplaySize160x160, DisplaySize320x132, DualDisplaySize, NewZeroed},
+    Ssd1320, Ssd1320z2,
+};
 
-use core::convert::TryInto;
+use core::{
+    cmp::{max, min},
+    convert::TryInto,
+    iter,
+    marker::PhantomData,
+    ops::Range,
+};
 
-use display_interface::{DisplayError, WriteOnlyDataCommand};
+use display_interface::WriteOnlyDataCommand;
 use embedded_hal::{blocking::delay::DelayMs, digital::v2::OutputPin};
 
 use embedded_graphics_core::{
     pixelcolor::{Gray4, GrayColor},
     prelude::*,
+    primitives::Rectangle,
 };
 
-#[derive(Copy, Clone, Debug)]
-pub struct BufferedSsd1320z2<DI, CS1, CS2> {
-    display: Ssd1320z2<DI, CS1, CS2>,
-    framebuffer: [u8; ssd1320z2::NUM_BUF_COLS as usize * ssd1320z2::NUM_PIXELS_ROWS as usize],
+/// Buffered graphics mode, drawing happens into a Gray4 framebuffer which is sent to the
+/// display by `flush`
+///
+/// The mode tracks the bounding box of the pixels drawn since the last `flush` separately
+/// for each controller, so only that part of the framebuffer is sent and a controller
+/// without any change is not addressed at all.
+///
+/// The framebuffer is stored in `BUFFER`, by default an array owned by the mode, or any
+/// storage provided by the caller, such as a `&'static mut [u8]`.
+///
+/// With [`enable_diff_flush`](Ssd1320z2::enable_diff_flush) the mode also keeps a shadow
+/// copy of the display RAM in `SHADOW` and sends only the bytes which differ from it.
+#[derive(Debug)]
+pub struct BufferedGraphicsMode<
+    SIZE,
+    BUFFER = <SIZE as DisplaySize>::Buffer,
+    SHADOW = &'static mut [u8],
+> where
+    SIZE: DisplaySize,
+{
+    buffer: BUFFER,
+    dirty: DirtyArea,
+    shadow: Option<Shadow<SHADOW>>,
+    size: PhantomData<SIZE>,
+}
+
+/// Copy of the framebuffer content last sent to the display
+#[derive(Debug)]
+struct Shadow<STORAGE> {
+    storage: STORAGE,
+    // The display RAM content is unknown until the first flush after enabling the shadow
+    valid: bool,
+}
+
+/// Unchanged bytes between two changed spans of a row which are cheaper to resend than
+/// setting up a new window, which takes the 3 byte column and row address commands
+const SPAN_MERGE_GAP: usize = 6;
+
+impl<SIZE, BUFFER, SHADOW> BufferedGraphicsMode<SIZE, BUFFER, SHADOW>
+where
+    SIZE: DisplaySize,
+{
+    /// Length of the framebuffer and the shadow copy in bytes
+    const LEN: usize = SIZE::WIDTH as usize * SIZE::HEIGHT as usize / 2;
+}
+
+impl<SIZE, BUFFER, SHADOW> BufferedGraphicsMode<SIZE, BUFFER, SHADOW>
+where
+    SIZE: DualDisplaySize,
+    BUFFER: AsMut<[u8]>,
+{
+    /// Create a new buffered graphics mode instance using the given storage of at least
+    /// [`LEN`](Self::LEN) bytes, the framebuffer is cleared and the whole of it is sent by the
+    /// first `flush` as the display RAM content is unknown
+    fn with_buffer(mut buffer: BUFFER) -> Self {
+        buffer.as_mut()[..Self::LEN].fill(0);
+
+        Self {
+            buffer,
+            dirty: DirtyArea::full::<SIZE>(),
+            shadow: None,
+            size: PhantomData,
+        }
+    }
+}
+
+/// Bounding boxes of the framebuffer pixels changed since the last flush in the left and
+/// the right half of the framebuffer
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct DirtyArea([Option<Frame>; 2]);
+
+impl DirtyArea {
+    /// Areas covering the whole display
+    pub fn full<SIZE>() -> Self
+    where
+        SIZE: DualDisplaySize,
+    {
+        let left = Frame {
+            start: (0, 0),
+            end: (SIZE::Half::WIDTH - 1, SIZE::HEIGHT - 1),
+        };
+        let right = Frame {
+            start: (SIZE::Half::WIDTH, 0),
+            end: (SIZE::WIDTH - 1, SIZE::HEIGHT - 1),
+        };
+
+        DirtyArea([Some(left), Some(right)])
+    }
+
+    /// Extend the area of a half to cover the pixel at `x`, `y`
+    pub fn add(&mut self, half: usize, x: u16, y: u16) {
+        self.0[half] = Some(match self.0[half] {
+            Some(Frame { start, end }) => Frame {
+                start: (min(start.0, x), min(start.1, y)),
+                end: (max(end.0, x), max(end.1, y)),
+            },
+            None => Frame {
+                start: (x, y),
+                end: (x, y),
+            },
+        });
+    }
+
+    /// Extend the areas to cover a frame which may cross the middle
+    pub fn add_frame(&mut self, frame: Frame, half_width: u16) {
+        let Frame { start, end } = frame;
+        if start.0 < half_width && end.0 >= half_width {
+            self.add(0, start.0, start.1);
+            self.add(0, half_width - 1, end.1);
+            self.add(1, half_width, start.1);
+            self.add(1, end.0, end.1);
+        } else {
+            let half = usize::from(start.0 >= half_width);
+            self.add(half, start.0, start.1);
+            self.add(half, end.0, end.1);
+        }
+    }
+
+    /// Whether no area is tracked
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(Option::is_none)
+    }
+
+    /// Areas to be flushed, widened to whole framebuffer bytes and limited to `height` rows,
+    /// the tracked areas are cleared down to the rows below `height`, which stay tracked
+    /// until they are shown again.
+    ///
+    /// Areas of both halves covering the same rows and meeting in the middle are merged into
+    /// the first one, it costs no more data and saves a window setup.
+    pub fn take(&mut self, height: u16, half_width: u16) -> [Option<Frame>; 2] {
+        let mut areas = [None; 2];
+        for (area, dirty) in areas.iter_mut().zip(self.0.iter_mut()) {
+            let Frame { start, end } = match dirty.take() {
+                Some(frame) => frame,
+                None => continue,
+            };
+            if end.1 >= height {
+                *dirty = Some(Frame {
+                    start: (start.0, max(start.1, height)),
+                    end,
+                });
+            }
+            if start.1 < height {
+                *area = Some(Frame {
+                    start: (start.0 & !1, start.1),
+                    end: (end.0 | 1, min(end.1, height - 1)),
+                });
+            }
+        }
+        if let [Some(left), Some(right)] = areas {
+            if left.end.0 == half_width - 1
+                && right.start.0 == half_width
+                && (left.start.1, left.end.1) == (right.start.1, right.end.1)
+            {
+                return [
+                    Some(Frame {
+                        start: left.start,
+                        end: right.end,
+                    }),
+                    None,
+                ];
+            }
+        }
+
+        areas
+    }
+}
+
+impl<SIZE, BUFFER, SHADOW> InitializedMode for BufferedGraphicsMode<SIZE, BUFFER, SHADOW>
+where
+    SIZE: DualDisplaySize,
+{
+    fn invalidate(&mut self) {
+        self.dirty = DirtyArea::full::<SIZE>();
+        if let Some(shadow) = &mut self.shadow {
+            shadow.valid = false;
+        }
+    }
+}
+
+/// SSD1320z2 driver in the buffered graphics mode
+pub type BufferedSsd1320z2<
+    DI,
+    CS1,
+    CS2,
+    SIZE = DisplaySize320x132,
+    BUFFER = <SIZE as DisplaySize>::Buffer,
+    SHADOW = &'static mut [u8],
+> = Ssd1320z2<DI, CS1, CS2, SIZE, BufferedGraphicsMode<SIZE, BUFFER, SHADOW>>;
+
+impl<DI, CS1, CS2, SIZE> Ssd1320z2<DI, CS1, CS2, SIZE, RawMode>
+where
+    SIZE: DualDisplaySize,
+{
+    /// Convert the display into the buffered graphics mode with a cleared framebuffer.
+    ///
+    /// The framebuffer is a part of the driver, use
+    /// [`into_buffered_graphics_mode_with_buffer`](Self::into_buffered_graphics_mode_with_buffer)
+    /// to keep it out of the stack.
+    pub fn into_buffered_graphics_mode(self) -> BufferedSsd1320z2<DI, CS1, CS2, SIZE> {
+        self.into_mode(BufferedGraphicsMode::with_buffer(NewZeroed::new_zeroed()))
+    }
+
+    /// Convert the display into the buffered graphics mode drawing into the given storage,
+    /// which is cleared.
+    ///
+    /// The storage can be a `&'static mut [u8]` placed in a `static` or a specific RAM
+    /// section, or anything else implementing `AsMut<[u8]>` and `AsRef<[u8]>`.
+    ///
+    /// A storage shorter than `SIZE::WIDTH * SIZE::HEIGHT / 2` bytes is rejected with
+    /// [`Error::InvalidParameter`], the display is returned with the error.
+    #[allow(clippy::type_complexity)]
+    pub fn into_buffered_graphics_mode_with_buffer<BUFFER>(
+        self,
+        buffer: BUFFER,
+    ) -> Result<BufferedSsd1320z2<DI, CS1, CS2, SIZE, BUFFER>, (Error<CS1::Error>, Self)>
+    where
+        CS1: OutputPin,
+        BUFFER: AsMut<[u8]> + AsRef<[u8]>,
+    {
+        if buffer.as_ref().len() < BufferedGraphicsMode::<SIZE, BUFFER>::LEN {
+            return Err((Error::InvalidParameter("framebuffer length"), self));
+        }
+
+        Ok(self.into_mode(BufferedGraphicsMode::with_buffer(buffer)))
+    }
 }
 
-impl<DI, CS1, CS2> BufferedSsd1320z2<DI, CS1, CS2>
+impl<DI, CS1, CS2, SIZE, BUFFER, SHADOW> BufferedSsd1320z2<DI, CS1, CS2, SIZE, BUFFER, SHADOW>
+where
+    SIZE: DualDisplaySize,
+{
+    /// Convert the display into the raw mode, the framebuffer is dropped.
+    pub fn into_raw_mode(self) -> Ssd1320z2<DI, CS1, CS2, SIZE, RawMode> {
+        self.into_raw_mode_and_buffer().0
+    }
+
+    /// Convert the display into the raw mode and return the framebuffer storage.
+    pub fn into_raw_mode_and_buffer(self) -> (Ssd1320z2<DI, CS1, CS2, SIZE, RawMode>, BUFFER) {
+        let Ssd1320z2 {
+            controllers,
+            scroll_area,
+            zoom,
+            mode,
+        } = self;
+        let display = Ssd1320z2 {
+            controllers,
+            scroll_area,
+            zoom,
+            mode: RawMode,
+        };
+
+        (display, mode.buffer)
+    }
+}
+
+impl<DI, CS1, CS2, SIZE, BUFFER, SHADOW> BufferedSsd1320z2<DI, CS1, CS2, SIZE, BUFFER, SHADOW>
 where
     DI: WriteOnlyDataCommand,
     CS1: OutputPin,
-    CS2: OutputPin,
+    CS2: OutputPin<Error = CS1::Error>,
+    SIZE: DualDisplaySize,
+    BUFFER: AsRef<[u8]>,
+    SHADOW: AsMut<[u8]>,
+{
+    /// Updates the display from the framebuffer.
+    ///
+    /// Only the bounding box of the pixels drawn since the previous flush is sent, and
+    /// only the upper half of the framebuffer in zoom in mode.
+    ///
+    /// With the diff flush enabled, the bytes of those areas are compared with the shadow
+    /// copy of the display RAM and only the changed spans of rows are sent.
+    pub fn flush(&mut self) -> Result<(), Error<CS1::Error>> {
+        let height = self.canvas_height();
+        let Ssd1320z2 {
+            controllers, mode, ..
+        } = self;
+        let buffer = mode.buffer.as_ref();
+        let areas = mode.dirty.take(height, SIZE::Half::WIDTH);
+        for &frame in areas.iter().flatten() {
+            match &mut mode.shadow {
+                Some(Shadow {
+                    storage,
+                    valid: true,
+                }) => send_changes(controllers, buffer, storage.as_mut(), frame)?,
+                _ => send_frame(controllers, buffer, frame)?,
+            }
+        }
+        // Rows hidden in zoom in mode are sent later, until then the display RAM differs
+        // from the framebuffer in more than the sent areas
+        let shown = mode.dirty.is_empty();
+        if let Some(shadow) = mode.shadow.as_mut().filter(|shadow| shown && !shadow.valid) {
+            // Outside of the sent areas the display already shows the framebuffer
+            let len = BufferedGraphicsMode::<SIZE, BUFFER, SHADOW>::LEN;
+            shadow.storage.as_mut()[..len].copy_from_slice(&buffer[..len]);
+            shadow.valid = true;
+        }
+
+        Ok(())
+    }
+
+    /// Updates an area of the display, in the coordinates of the current rotation, from
+    /// the framebuffer regardless of the areas drawn since the previous flush.
+    ///
+    /// The area is clipped to the display and widened to whole display RAM columns of two
+    /// pixels, the added pixels are sent from the framebuffer as well.
+    pub fn flush_area(&mut self, area: &Rectangle) -> Result<(), Error<CS1::Error>> {
+        let Frame { start, end } = match self.visible_frame(area) {
+            Some(frame) => frame,
+            None => return Ok(()),
+        };
+        let frame = Frame {
+            start: (start.0 & !1, start.1),
+            end: (end.0 | 1, end.1),
+        };
+        let Ssd1320z2 {
+            controllers, mode, ..
+        } = self;
+        let buffer = mode.buffer.as_ref();
+        send_frame(controllers, buffer, frame)?;
+        if let Some(Shadow {
+            storage,
+            valid: true,
+        }) = &mut mode.shadow
+        {
+            // Keep the shadow copy matching the display RAM
+            let row_len = (SIZE::WIDTH / 2) as usize;
+            for y in frame.start.1 as usize..=frame.end.1 as usize {
+                let bytes = y * row_len + frame.start.0 as usize / 2
+                    ..y * row_len + frame.end.0 as usize / 2 + 1;
+                storage.as_mut()[bytes.clone()].copy_from_slice(&buffer[bytes]);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Send a frame of a packed Gray4 framebuffer as one window
+fn send_frame<DI, CS1, CS2, SIZE>(
+    controllers: &mut Controllers<DI, CS1, CS2, SIZE>,
+    buffer: &[u8],
+    frame: Frame,
+) -> Result<(), Error<CS1::Error>>
+where
+    DI: WriteOnlyDataCommand,
+    CS1: OutputPin,
+    CS2: OutputPin<Error = CS1::Error>,
+    SIZE: DualDisplaySize,
+{
+    let Frame { start, end } = frame;
+    let row_len = (SIZE::WIDTH / 2) as usize;
+    controllers.set_draw_area(start, end)?;
+    if start.0 == 0 && end.0 == SIZE::WIDTH - 1 {
+        // Whole rows are contiguous in the framebuffer
+        let rows = start.1 as usize * row_len..(end.1 as usize + 1) * row_len;
+        controllers.draw(&buffer[rows])
+    } else {
+        let columns = start.0 as usize / 2..end.0 as usize / 2 + 1;
+        for row in buffer[..(end.1 as usize + 1) * row_len]
+            .chunks(row_len)
+            .skip(start.1 as usize)
+        {
+            controllers.draw(&row[columns.clone()])?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Send the bytes of a frame which differ from the shadow copy and update it.
+///
+/// Changed bytes of a row closer than [`SPAN_MERGE_GAP`] are sent as one span, the same
+/// spans of consecutive rows are sent as one window.
+fn send_changes<DI, CS1, CS2, SIZE>(
+    controllers: &mut Controllers<DI, CS1, CS2, SIZE>,
+    buffer: &[u8],
+    shadow: &mut [u8],
+    frame: Frame,
+) -> Result<(), Error<CS1::Error>>
+where
+    DI: WriteOnlyDataCommand,
+    CS1: OutputPin,
+    CS2: OutputPin<Error = CS1::Error>,
+    SIZE: DualDisplaySize,
+{
+    let columns = (frame.start.0 / 2) as usize..(frame.end.0 / 2) as usize + 1;
+    // Span waiting to be sent with the rows it changed in
+    let mut pending: Option<((usize, usize), (u16, u16))> = None;
+    for y in frame.start.1..=frame.end.1 {
+        let (first, second) = {
+            let mut spans = changed_spans::<SIZE>(buffer, shadow, y, columns.clone());
+            (spans.next(), spans.next())
+        };
+        if let (Some((span, rows)), Some(first), None) = (pending, first, second) {
+            if span == first {
+                pending = Some((span, (rows.0, y)));
+                continue;
+            }
+        }
+        if let Some((span, rows)) = pending.take() {
+            send_window(controllers, buffer, shadow, span, rows)?;
+        }
+        if second.is_none() {
+            pending = first.map(|span| (span, (y, y)));
+            continue;
+        }
+        loop {
+            let span = changed_spans::<SIZE>(buffer, shadow, y, columns.clone()).next();
+            match span {
+                Some(span) => send_window(controllers, buffer, shadow, span, (y, y))?,
+                None => break,
+            }
+        }
+    }
+    if let Some((span, rows)) = pending {
+        send_window(controllers, buffer, shadow, span, rows)?;
+    }
+
+    Ok(())
+}
+
+/// Send a window of framebuffer byte columns `span` and `rows` and copy it to the shadow
+fn send_window<DI, CS1, CS2, SIZE>(
+    controllers: &mut Controllers<DI, CS1, CS2, SIZE>,
+    buffer: &[u8],
+    shadow: &mut [u8],
+    span: (usize, usize),
+    rows: (u16, u16),
+) -> Result<(), Error<CS1::Error>>
+where
+    DI: WriteOnlyDataCommand,
+    CS1: OutputPin,
+    CS2: OutputPin<Error = CS1::Error>,
+    SIZE: DualDisplaySize,
+{
+    let row_len = (SIZE::WIDTH / 2) as usize;
+    controllers.set_draw_area((span.0 as u16 * 2, rows.0), (span.1 as u16 * 2 + 1, rows.1))?;
+    for y in rows.0..=rows.1 {
+        let bytes = y as usize * row_len + span.0..y as usize * row_len + span.1 + 1;
+        controllers.draw(&buffer[bytes.clone()])?;
+        shadow[bytes.clone()].copy_from_slice(&buffer[bytes]);
+    }
+
+    Ok(())
+}
+
+/// Inclusive spans of the byte `columns` of row `y` which differ between the framebuffer
+/// and the shadow copy, spans closer than [`SPAN_MERGE_GAP`] are merged
+fn changed_spans<'a, SIZE>(
+    buffer: &'a [u8],
+    shadow: &'a [u8],
+    y: u16,
+    columns: Range<usize>,
+) -> impl Iterator<Item = (usize, usize)> + 'a
+where
+    SIZE: DisplaySize,
+{
+    let row = y as usize * (SIZE::WIDTH / 2) as usize;
+    let bytes = row + columns.start..row + columns.end;
+    let mut changed = buffer[bytes.clone()]
+        .iter()
+        .zip(&shadow[bytes])
+        .enumerate()
+        .filter(|(_, (byte, old))| byte != old)
+        .map(move |(index, _)| columns.start + index)
+        .peekable();
+
+    iter::from_fn(move || {
+        let first = changed.next()?;
+        let mut last = first;
+        while let Some(next) = changed.next_if(|&next| next - last <= SPAN_MERGE_GAP) {
+            last = next;
+        }
+
+        Some((first, last))
+    })
+}
+
+impl<DI, CS1, CS2, SIZE, BUFFER, SHADOW> BufferedSsd1320z2<DI, CS1, CS2, SIZE, BUFFER, SHADOW>
+where
+    SIZE: DualDisplaySize,
+{
+    /// Keep a copy of the display RAM content in the given storage and send only the bytes
+    /// which differ from it on `flush`.
+    ///
+    /// The storage can be a `&'static mut [u8]` like the framebuffer, or anything else
+    /// implementing `AsMut<[u8]>` and `AsRef<[u8]>`, a previously enabled shadow storage is
+    /// dropped. The next flush sends the drawn areas as a whole and fills the shadow copy,
+    /// the following ones compare the framebuffer with it. The display RAM must not be
+    /// written by other means while the diff flush is enabled.
+    ///
+    /// A storage shorter than `SIZE::WIDTH * SIZE::HEIGHT / 2` bytes is rejected with
+    /// [`Error::InvalidParameter`], the display is returned with the error.
+    #[allow(clippy::type_complexity)]
+    pub fn enable_diff_flush<STORAGE>(
+        self,
+        shadow: STORAGE,
+    ) -> Result<BufferedSsd1320z2<DI, CS1, CS2, SIZE, BUFFER, STORAGE>, (Error<CS1::Error>, Self)>
+    where
+        CS1: OutputPin,
+        STORAGE: AsMut<[u8]> + AsRef<[u8]>,
+    {
+        if shadow.as_ref().len() < BufferedGraphicsMode::<SIZE, BUFFER, SHADOW>::LEN {
+            return Err((Error::InvalidParameter("shadow length"), self));
+        }
+        let Ssd1320z2 {
+            controllers,
+            scroll_area,
+            zoom,
+            mode,
+        } = self;
+        let mode = BufferedGraphicsMode {
+            buffer: mode.buffer,
+            dirty: mode.dirty,
+            shadow: Some(Shadow {
+                storage: shadow,
+                valid: false,
+            }),
+            size: PhantomData,
+        };
+
+        Ok(Ssd1320z2 {
+            controllers,
+            scroll_area,
+            zoom,
+            mode,
+        })
+    }
+
+    /// Go back to sending whole drawn areas on `flush` and return the shadow storage.
+    pub fn disable_diff_flush(&mut self) -> Option<SHADOW> {
+        self.mode.shadow.take().map(|shadow| shadow.storage)
+    }
+
+    /// Framebuffer frame of the visible part of an area in the coordinates of the
+    /// current rotation
+    fn visible_frame(&self, area: &Rectangle) -> Option<Frame> {
+        let Frame { start, end } = rectangle_to_frame(&area.intersection(&self.bounding_box()))?;
+        if self.rotation().is_portrait() {
+            // Same turn as in `draw_iter`
+            let last_column = SIZE::WIDTH - 1;
+            Some(Frame {
+                start: (last_column - end.1, start.0),
+                end: (last_column - start.1, end.0),
+            })
+        } else {
+            Some(Frame { start, end })
+        }
+    }
+}
+
+impl<DI, CS1, CS2, SIZE, BUFFER, SHADOW> OriginDimensions
+    for BufferedSsd1320z2<DI, CS1, CS2, SIZE, BUFFER, SHADOW>
+where
+    SIZE: DualDisplaySize,
+{
+    fn size(&self) -> Size {
+        let height = self.canvas_height() as u32;
+        if self.rotation().is_portrait() {
+            Size::new(height, SIZE::WIDTH as u32)
+        } else {
+            Size::new(SIZE::WIDTH as u32, height)
+        }
+    }
+}
+
+impl<DI, CS1, CS2, SIZE, BUFFER, SHADOW> DrawTarget
+    for BufferedSsd1320z2<DI, CS1, CS2, SIZE, BUFFER, SHADOW>
+where
+    CS1: OutputPin,
+    SIZE: DualDisplaySize,
+    BUFFER: AsMut<[u8]>,
+{
+    type Color = Gray4;
+
+    type Error = Error<CS1::Error>;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let height = self.canvas_height();
+        let portrait = self.rotation().is_portrait();
+        let half_width = SIZE::Half::WIDTH;
+        let BufferedGraphicsMode { buffer, dirty, .. } = &mut self.mode;
+        let mark = |x, y| dirty.add(usize::from(x >= half_width), x, y);
+        if portrait {
+            // Turn the portrait image by 90 degrees clockwise, for 270 degrees the controllers
+            // turn it by additional 180 degrees.
+            let last_column = SIZE::WIDTH as i32 - 1;
+            let pixels = pixels
+                .into_iter()
+                .map(|Pixel(p, color)| Pixel(Point::new(last_column - p.y, p.x), color));
+            draw_pixels(buffer.as_mut(), SIZE::WIDTH, height, pixels, mark);
+        } else {
+            draw_pixels(buffer.as_mut(), SIZE::WIDTH, height, pixels, mark);
+        }
+
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        if self.rotation().is_portrait() {
+            // Rows of the area are columns of the framebuffer, draw pixel by pixel
+            let pixels = area
+                .points()
+                .zip(colors)
+                .map(|(point, color)| Pixel(point, color));
+            return self.draw_iter(pixels);
+        }
+        let visible = area.intersection(&self.bounding_box());
+        if let Some(frame) = rectangle_to_frame(&visible) {
+            let skip = (
+                (visible.top_left.x - area.top_left.x) as usize,
+                (visible.top_left.y - area.top_left.y) as usize,
+            );
+            fill_pixels(
+                self.mode.buffer.as_mut(),
+                SIZE::WIDTH,
+                frame,
+                area.size.width as usize,
+                skip,
+                colors,
+            );
+            self.mode.dirty.add_frame(frame, SIZE::Half::WIDTH);
+        }
+
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        if let Some(frame) = self.visible_frame(area) {
+            fill_frame(self.mode.buffer.as_mut(), SIZE::WIDTH, frame, color.luma());
+            self.mode.dirty.add_frame(frame, SIZE::Half::WIDTH);
+        }
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        let height = self.canvas_height();
+        let len = (SIZE::WIDTH / 2 * height) as usize;
+        let luma = color.luma();
+        self.mode.buffer.as_mut()[..len].fill(luma | luma << 4);
+        self.mode
+            .dirty
+            .add_frame(Frame::new(SIZE::WIDTH, height), SIZE::Half::WIDTH);
+
+        Ok(())
+    }
+}
+
+/// Inclusive pixel frame of a rectangle, `None` for an empty one
+fn rectangle_to_frame(rectangle: &Rectangle) -> Option<Frame> {
+    let bottom_right = rectangle.bottom_right()?;
+    let top_left = rectangle.top_left;
+
+    Some(Frame {
+        start: (top_left.x as u16, top_left.y as u16),
+        end: (bottom_right.x as u16, bottom_right.y as u16),
+    })
+}
+
+/// Fill a frame of a packed Gray4 framebuffer `width` pixels wide with one gray level,
+/// whole bytes are written at once and only the odd edges are merged with their neighbours
+fn fill_frame(framebuffer: &mut [u8], width: u16, frame: Frame, luma: u8) {
+    let Frame { start, end } = frame;
+    let byte = luma | luma << 4;
+    for row in framebuffer
+        .chunks_mut(width as usize / 2)
+        .take(end.1 as usize + 1)
+        .skip(start.1 as usize)
+    {
+        let mut first = start.0 as usize / 2;
+        let mut last = end.0 as usize / 2 + 1;
+        if start.0 % 2 == 1 {
+            row[first] = row[first] & 0x0f | luma << 4;
+            first += 1;
+        }
+        if end.0 % 2 == 0 {
+            last -= 1;
+            row[last] = row[last] & 0xf0 | luma;
+        }
+        if first < last {
+            row[first..last].fill(byte);
+        }
+    }
+}
+
+/// Write the colors of an area `area_width` pixels wide, of which `frame` of a packed Gray4
+/// framebuffer is visible, `skip` is the offset of the visible part inside of the area.
+///
+/// Colors are packed in pairs, only the odd edges are merged with their neighbours. Drawing
+/// stops when the colors run out.
+fn fill_pixels<I>(
+    framebuffer: &mut [u8],
+    width: u16,
+    frame: Frame,
+    area_width: usize,
+    skip: (usize, usize),
+    colors: I,
+) -> Option<()>
+where
+    I: IntoIterator<Item = Gray4>,
+{
+    let Frame { start, end } = frame;
+    let visible_width = (end.0 - start.0 + 1) as usize;
+    let skip_right = area_width - visible_width - skip.0;
+    let mut colors = colors.into_iter().map(|color| color.luma());
+    if skip.1 > 0 {
+        colors.nth(skip.1 * area_width - 1)?;
+    }
+    for row in framebuffer
+        .chunks_mut(width as usize / 2)
+        .take(end.1 as usize + 1)
+        .skip(start.1 as usize)
+    {
+        if skip.0 > 0 {
+            colors.nth(skip.0 - 1)?;
+        }
+        let mut x = start.0 as usize;
+        if x % 2 == 1 {
+            row[x / 2] = row[x / 2] & 0x0f | colors.next()? << 4;
+            x += 1;
+        }
+        while x < end.0 as usize {
+            let low = colors.next()?;
+            row[x / 2] = match colors.next() {
+                Some(high) => low | high << 4,
+                None => {
+                    row[x / 2] = row[x / 2] & 0xf0 | low;
+                    return None;
+                }
+            };
+            x += 2;
+        }
+        if x == end.0 as usize {
+            row[x / 2] = row[x / 2] & 0xf0 | colors.next()?;
+        }
+        if skip_right > 0 {
+            colors.nth(skip_right - 1)?;
+        }
+    }
+
+    Some(())
+}
+
+/// SSD1320 driver with a Gray4 framebuffer
+#[derive(Clone, Debug)]
+pub struct BufferedSsd1320<DI, SIZE = DisplaySize160x160>
+where
+    SIZE: DisplaySize,
 {
-    /// Create a SSD1320z2 interface
-    pub fn new(interface: DI, cs1: CS1, cs2: CS2) -> Self {
+    display: Ssd1320<DI, SIZE>,
+    framebuffer: SIZE::Buffer,
+}
+
+impl<DI, SIZE> BufferedSsd1320<DI, SIZE>
+where
+    DI: WriteOnlyDataCommand,
+    SIZE: DisplaySize,
+{
+    /// Create a SSD1320 interface
+    pub fn new(interface: DI, size: SIZE) -> Self {
         Self {
-            display: Ssd1320z2::new(interface, cs1, cs2),
-            framebuffer: [0; ssd1320z2::NUM_BUF_COLS as usize
-                * ssd1320z2::NUM_PIXELS_ROWS as usize],
+            display: Ssd1320::new(interface, size),
+            framebuffer: NewZeroed::new_zeroed(),
         }
     }
 
-    /// Reset and init the display.
+    /// Reset and init the display with the default configuration.
     pub fn init<RST, DELAY, PinE>(
         &mut self,
         rst: &mut RST,
         delay: &mut DELAY,
-    ) -> Result<(), Error<(), PinE>>
+    ) -> Result<(), Error<PinE>>
+    where
+        RST: OutputPin<Error = PinE>,
+        DELAY: DelayMs<u8>,
+    {
+        self.init_with_config(rst, delay, &Config::default())
+    }
+
+    /// Reset and init the display with the given configuration.
+    pub fn init_with_config<RST, DELAY, PinE>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+        config: &Config,
+    ) -> Result<(), Error<PinE>>
     where
         RST: OutputPin<Error = PinE>,
         DELAY: DelayMs<u8>,
     {
         self.display.reset(rst, delay)?;
-        self.display.init();
+        self.display.init(config).map_err(Error::with_pin_error)?;
 
         Ok(())
     }
 
     /// Updates the display from the framebuffer.
-    pub fn flush(&mut self) -> Result<(), DisplayError> {
-        self.display.draw(&self.framebuffer)
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.display
+            .set_draw_area((0, 0), ((SIZE::WIDTH - 1) as u8, (SIZE::HEIGHT - 1) as u8))?;
+        self.display.draw(self.framebuffer.as_ref())
     }
 }
 
-impl<DI, CS1, CS2> OriginDimensions for BufferedSsd1320z2<DI, CS1, CS2> {
+impl<DI, SIZE> OriginDimensions for BufferedSsd1320<DI, SIZE>
+where
+    SIZE: DisplaySize,
+{
     fn size(&self) -> Size {
-        Size::new(
-            ssd1320z2::NUM_PIXELS_COLS as u32,
-            ssd1320z2::NUM_PIXELS_ROWS as u32,
-        )
+        Size::new(SIZE::WIDTH as u32, SIZE::HEIGHT as u32)
     }
 }
 
-impl<DI, CS1, CS2> DrawTarget for BufferedSsd1320z2<DI, CS1, CS2> {
+impl<DI, SIZE> DrawTarget for BufferedSsd1320<DI, SIZE>
+where
+    SIZE: DisplaySize,
+{
     type Color = Gray4;
 
-    type Error = DisplayError;
+    type Error = Error;
 
     fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = Pixel<Self::Color>>,
     {
-        for Pixel(coord, color) in pixels.into_iter() {
-            const X_END: u32 = ssd1320z2::PIXEL_COL_MAX as u32;
-            const Y_END: u32 = ssd1320z2::PIXEL_ROW_MAX as u32;
-            // Check if the pixel coordinates are out of bounds (negative or greater than
-            // (320, 132)). `DrawTarget` implementation are required to discard any out of bounds
-            // pixels without returning an error or causing a panic.
-            if let Ok((x @ 0..=X_END, y @ 0..=Y_END)) = coord.try_into() {
-                // Calculate the index in the framebuffer.
-                let index = x / 2 + y * ssd1320z2::NUM_BUF_COLS as u32;
-                let shift = if x % 2 == 0 { 0 } else { 4 };
-                self.framebuffer[index as usize] &= !(0xf << shift);
-                self.framebuffer[index as usize] |= (color.luma() << shift);
-            }
-        }
+        draw_pixels(
+            self.framebuffer.as_mut(),
+            SIZE::WIDTH,
+            SIZE::HEIGHT,
+            pixels,
+            |_, _| {},
+        );
 
         Ok(())
     }
 }
+
+/// Write pixels into the upper `height` rows of a packed Gray4 framebuffer `width`
+/// pixels wide, `mark` is called with the coordinates of every written pixel
+fn draw_pixels<I, F>(framebuffer: &mut [u8], width: u16, height: u16, pixels: I, mut mark: F)
+where
+    I: IntoIterator<Item = Pixel<Gray4>>,
+    F: FnMut(u16, u16),
+{
+    let width = width as u32;
+    let height = height as u32;
+
+    for Pixel(coord, color) in pixels.into_iter() {
+        // Check if the pixel coordinates are out of bounds (negative or greater than
+        // display size). `DrawTarget` implementation are required to discard any out of bounds
+        // pixels without returning an error or causing a panic.
+        if let Ok((x, y)) = coord.try_into() {
+            let (x, y): (u32, u32) = (x, y);
+            if x >= width || y >= height {
+                continue;
+            }
+            // Calculate the index in the framebuffer.
+            let index = (x / 2 + y * width / 2) as usize;
+            let shift = if x % 2 == 0 { 0 } else { 4 };
+            framebuffer[index] &= !(0xf << shift);
+            framebuffer[index] |= color.luma() << shift;
+            mark(x as u16, y as u16);
+        }
+    }
+}
@@ -1,12 +1,21 @@
 //! Display commands
+//!
+//! Commands are normally sent by the driver methods. They can also be built with the checked
+//! constructors and sent directly with `send_command` to experiment with the registers.
 
-use display_interface::{DataFormat::U8, DisplayError, WriteOnlyDataCommand};
+use crate::consts::ssd1320;
+use crate::error::Error;
+use crate::grayscale::GrayScaleTable;
 
-/// SSD1320 Commands
+use core::fmt;
+
+use display_interface::{DataFormat::U8, WriteOnlyDataCommand};
 
-/// Commands
-#[derive(Debug, Copy, Clone)]
-#[allow(dead_code)]
+/// SSD1320 Commands
+///
+/// The variants with a limited range of values are checked when the command is sent, the
+/// checked constructors report an out of range value already when the command is built.
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Command {
     /// Set addressing mode
     AddressMode(AddrMode),
@@ -18,6 +27,14 @@ pub enum Command {
     RowAddress(u8, u8),
     /// Set portrait addressing mode
     PortraitAddressMode(PortraitAddrMode),
+    /// Setup continuous horizontal scroll.
+    /// Values are direction, start row, time interval between each scroll step,
+    /// end row, start column and end column
+    HorizontalScrollSetup(ScrollDirection, u8, ScrollInterval, u8, u8, u8),
+    /// Activate (true) or deactivate (false) scrolling
+    ScrollActive(bool),
+    /// Set fade out or blinking mode
+    FadeMode(FadeMode),
     /// Set contrast. Higher number is higher contrast. Default = 0x7F
     Contrast(u8),
     /// Reverse columns from 79-0
@@ -29,17 +46,18 @@ pub enum Command {
     AllOn(bool),
     /// Invert display.
     Invert(bool),
-    /// Set multipex ratio from 16-160 (MUX+1)
+    /// Set multiplex ratio from 16-160, the number of scanned lines (MUX+1)
     Multiplex(u8),
     /// Select external or internal I REF.
-    InternalIref(bool),
+    Iref(Iref),
     /// Turn display on or off.
     DisplayOn(bool),
     /// Set pre-charge volage level
     /// must be smaller than COM deselect volage level
     PreChargeLevel(PreChargeLvl),
-    /// No mapped command TODO: search or reverse meaning of command
-    VP,
+    /// Enable (true) or disable (false) the external capacitor on the VP pin which
+    /// stabilises the pre-charge voltage
+    PreChargeCapacitor(bool),
     /// The default Lineral Gray Scale table is in unit
     /// of DCLK's as follow
     /// GS0 level pulse width = 0
@@ -50,15 +68,20 @@ pub enum Command {
     /// GS14 level pulse width = 56
     /// GS15 level pulse width = 60
     LineralLUT,
+    /// Set user-defined gray scale table with pulse widths of GS1 to GS15
+    GrayScaleTable(GrayScaleTable),
     /// Scan from COM[n-1] to COM0 (where N is mux ratio)
     ReverseComDir(bool),
-    /// Set vertical shift
+    /// Set vertical shift by COM from 0-159
     DisplayOffset(u8),
     /// Set up display clock.
-    /// First value is oscillator frequency, increasing with higher value
+    /// First value is oscillator frequency from 0-15, increasing with higher value
     /// Second value is divide ratio (1, 2, 4, 8 ... 256)
-    DisplayClockDiv(u8, u8),
-    /// Set up phase 1 and 2 of precharge period. Each value must be in the range 1 - 15.
+    DisplayClockDiv(u8, u16),
+    /// Zoom in mode, each display RAM row is shown on two lines
+    ZoomIn(bool),
+    /// Set up phase 1 and 2 of precharge period. Phase 1 must be in the range 1 - 15,
+    /// phase 2 in the range 0 - 15.
     PreChargePeriod(u8, u8),
     /// Setup com hardware configuration
     /// First value indicates sequential (false) or alternative (true)
@@ -69,40 +92,206 @@ pub enum Command {
     VcomhDeselect(VcomhLevel),
     /// Display lock
     DisplayLock(bool),
+    /// No operation
+    Nop,
 }
 
 impl Command {
+    /// Setup column start and end address from 0-79
+    pub fn column_address(start: u8, end: u8) -> Result<Self, Error> {
+        Command::ColumnAddress(start, end).checked()
+    }
+
+    /// Setup row start and end address from 0-159
+    pub fn row_address(start: u8, end: u8) -> Result<Self, Error> {
+        Command::RowAddress(start, end).checked()
+    }
+
+    /// Setup continuous horizontal scroll of the rows from `start_row` to `end_row` (0-159)
+    /// and the columns from `start_column` to `end_column` (0-79)
+    pub fn horizontal_scroll_setup(
+        direction: ScrollDirection,
+        start_row: u8,
+        interval: ScrollInterval,
+        end_row: u8,
+        start_column: u8,
+        end_column: u8,
+    ) -> Result<Self, Error> {
+        Command::HorizontalScrollSetup(
+            direction,
+            start_row,
+            interval,
+            end_row,
+            start_column,
+            end_column,
+        )
+        .checked()
+    }
+
+    /// Set display start line from 0-159
+    pub fn start_line(line: u8) -> Result<Self, Error> {
+        Command::StartLine(line).checked()
+    }
+
+    /// Set multiplex ratio from 16-160, `ratio` lines are scanned
+    pub fn multiplex(ratio: u8) -> Result<Self, Error> {
+        Command::Multiplex(ratio).checked()
+    }
+
+    /// Set vertical shift by COM from 0-159
+    pub fn display_offset(offset: u8) -> Result<Self, Error> {
+        Command::DisplayOffset(offset).checked()
+    }
+
+    /// Set up display clock with oscillator frequency from 0-15 and divide ratio
+    /// (1, 2, 4, 8 ... 256)
+    pub fn display_clock_div(fosc: u8, ratio: u16) -> Result<Self, Error> {
+        Command::DisplayClockDiv(fosc, ratio).checked()
+    }
+
+    /// Set up phase 1 (1-15 DCLKs) and phase 2 (0-15 DCLKs) of precharge period
+    ///
+    /// A phase 2 of 0 is used by the init sequence of the SUR383S1000WG01 module.
+    pub fn precharge_period(phase1: u8, phase2: u8) -> Result<Self, Error> {
+        Command::PreChargePeriod(phase1, phase2).checked()
+    }
+
+    /// Return the command if its values are in the ranges accepted by the controller
+    fn checked(self) -> Result<Self, Error> {
+        self.check()?;
+        Ok(self)
+    }
+
+    /// Check the values of the command against the ranges accepted by the controller
+    fn check(&self) -> Result<(), Error> {
+        const MAX_COLUMN: u8 = ssd1320::BUF_COL_MAX as u8;
+        const MAX_ROW: u8 = 159;
+
+        let valid = match *self {
+            Command::ColumnAddress(start, end) => start <= MAX_COLUMN && end <= MAX_COLUMN,
+            Command::RowAddress(start, end) => start <= MAX_ROW && end <= MAX_ROW,
+            Command::HorizontalScrollSetup(_, start_row, _, end_row, start_col, end_col) => {
+                start_row <= end_row
+                    && end_row <= MAX_ROW
+                    && start_col <= end_col
+                    && end_col <= MAX_COLUMN
+            }
+            Command::StartLine(line) => line <= MAX_ROW,
+            Command::Multiplex(ratio) => (16..=MAX_ROW + 1).contains(&ratio),
+            Command::DisplayOffset(offset) => offset <= MAX_ROW,
+            Command::DisplayClockDiv(fosc, ratio) => {
+                fosc <= 0xF && ratio.is_power_of_two() && ratio <= 256
+            }
+            Command::PreChargePeriod(phase1, phase2) => {
+                (1..=0xF).contains(&phase1) && phase2 <= 0xF
+            }
+            _ => true,
+        };
+        if !valid {
+            return Err(Error::InvalidParameter(self.name()));
+        }
+
+        Ok(())
+    }
+
+    /// Name of the command used in errors
+    fn name(&self) -> &'static str {
+        match self {
+            Command::ColumnAddress(..) => "column address",
+            Command::RowAddress(..) => "row address",
+            Command::HorizontalScrollSetup(..) => "horizontal scroll setup",
+            Command::StartLine(_) => "start line",
+            Command::Multiplex(_) => "multiplex ratio",
+            Command::DisplayOffset(_) => "display offset",
+            Command::DisplayClockDiv(..) => "display clock divide ratio",
+            Command::PreChargePeriod(..) => "pre-charge period",
+            _ => "command",
+        }
+    }
+
     /// Send command to SSD1320
-    pub fn send<DI>(self, iface: &mut DI) -> Result<(), DisplayError>
+    ///
+    /// Out of range values are reported as [`Error::InvalidParameter`] before anything
+    /// is sent.
+    pub fn send<DI>(self, iface: &mut DI) -> Result<(), Error>
     where
         DI: WriteOnlyDataCommand,
     {
-        // Transform command into a fixed size array of 7 u8 and the real length for sending
+        self.check()?;
+
+        // Transform command into a fixed size array of 8 u8 and the real length for sending
         let (data, len) = match self {
-            Command::AddressMode(mode) => ([0x20, mode as u8, 0, 0, 0, 0, 0], 2),
-            Command::ColumnAddress(start, end) => ([0x21, start, end, 0, 0, 0, 0], 3),
-            Command::RowAddress(start, end) => ([0x22, start, end, 0, 0, 0, 0], 3),
-            Command::PortraitAddressMode(mode) => ([0x25, mode as u8, 0, 0, 0, 0, 0], 2),
-            Command::Contrast(val) => ([0x81, val, 0, 0, 0, 0, 0], 2),
-            Command::SegmentRemap(remap) => ([0xA0 | (remap as u8), 0, 0, 0, 0, 0, 0], 1),
-            Command::StartLine(line) => ([0xA2, 0x3F & line, 0, 0, 0, 0, 0], 2),
-            Command::AllOn(on) => ([0xA4 | (on as u8), 0, 0, 0, 0, 0, 0], 1),
-            Command::Invert(inv) => ([0xA6 | (inv as u8), 0, 0, 0, 0, 0, 0], 1),
-            Command::Multiplex(ratio) => ([0xA8, ratio, 0, 0, 0, 0, 0], 2),
-            Command::InternalIref(on) => ([0xAD, (on as u8) << 4, 0, 0, 0, 0, 0], 2),
-            Command::DisplayOn(on) => ([0xAE | (on as u8), 0, 0, 0, 0, 0, 0], 1),
-            Command::PreChargeLevel(level) => ([0xBC, level as u8, 0, 0, 0, 0, 0], 2),
-            Command::VP => ([0xBD, 0x03, 0, 0, 0, 0, 0], 2),
-            Command::LineralLUT => ([0xBF, 0, 0, 0, 0, 0, 0], 1),
-            Command::ReverseComDir(rev) => ([0xC0 | ((rev as u8) << 3), 0, 0, 0, 0, 0, 0], 1),
-            Command::DisplayOffset(offset) => ([0xD3, offset, 0, 0, 0, 0, 0], 2),
-            Command::DisplayClockDiv(fosc, div) => {
-                ([0xD5, ((0xF & fosc) << 4) | (0xF & div), 0, 0, 0, 0, 0], 2)
-            }
-            Command::PreChargePeriod(phase1, phase2) => (
-                [0xD9, ((0xF & phase2) << 4) | (0xF & phase1), 0, 0, 0, 0, 0],
+            Command::AddressMode(mode) => ([0x20, mode as u8, 0, 0, 0, 0, 0, 0], 2),
+            Command::ColumnAddress(start, end) => ([0x21, start, end, 0, 0, 0, 0, 0], 3),
+            Command::RowAddress(start, end) => ([0x22, start, end, 0, 0, 0, 0, 0], 3),
+            Command::PortraitAddressMode(mode) => ([0x25, mode as u8, 0, 0, 0, 0, 0, 0], 2),
+            Command::HorizontalScrollSetup(
+                dir,
+                start_row,
+                interval,
+                end_row,
+                start_col,
+                end_col,
+            ) => (
+                [
+                    0x26 | (dir as u8),
+                    0,
+                    start_row,
+                    interval as u8,
+                    end_row,
+                    start_col,
+                    end_col,
+                    0,
+                ],
+                8,
+            ),
+            Command::FadeMode(mode) => {
+                let value = match mode {
+                    FadeMode::Disabled => 0,
+                    FadeMode::FadeOut(interval) => 0b10 << 4 | interval as u8,
+                    FadeMode::Blinking(interval) => 0b11 << 4 | interval as u8,
+                };
+                ([0x23, value, 0, 0, 0, 0, 0, 0], 2)
+            }
+            Command::ScrollActive(active) => ([0x2E | (active as u8), 0, 0, 0, 0, 0, 0, 0], 1),
+            Command::Contrast(val) => ([0x81, val, 0, 0, 0, 0, 0, 0], 2),
+            Command::SegmentRemap(remap) => ([0xA0 | (remap as u8), 0, 0, 0, 0, 0, 0, 0], 1),
+            Command::StartLine(line) => ([0xA2, line, 0, 0, 0, 0, 0, 0], 2),
+            Command::AllOn(on) => ([0xA4 | (on as u8), 0, 0, 0, 0, 0, 0, 0], 1),
+            Command::Invert(inv) => ([0xA6 | (inv as u8), 0, 0, 0, 0, 0, 0, 0], 1),
+            Command::Multiplex(ratio) => ([0xA8, ratio - 1, 0, 0, 0, 0, 0, 0], 2),
+            Command::Iref(iref) => ([0xAD, iref as u8, 0, 0, 0, 0, 0, 0], 2),
+            Command::DisplayOn(on) => ([0xAE | (on as u8), 0, 0, 0, 0, 0, 0, 0], 1),
+            Command::PreChargeLevel(level) => ([0xBC, level as u8, 0, 0, 0, 0, 0, 0], 2),
+            Command::PreChargeCapacitor(on) => {
+                ([0xBD, if on { 0b11 } else { 0b00 }, 0, 0, 0, 0, 0, 0], 2)
+            }
+            Command::LineralLUT => ([0xBF, 0, 0, 0, 0, 0, 0, 0], 1),
+            Command::GrayScaleTable(table) => {
+                // The only command longer than 8 bytes
+                let mut data = [0xBE; 16];
+                data[1..].copy_from_slice(table.widths());
+                return iface.send_commands(U8(&data)).map_err(Error::Comm);
+            }
+            Command::ReverseComDir(rev) => ([0xC0 | ((rev as u8) << 3), 0, 0, 0, 0, 0, 0, 0], 1),
+            Command::DisplayOffset(offset) => ([0xD3, offset, 0, 0, 0, 0, 0, 0], 2),
+            Command::DisplayClockDiv(fosc, ratio) => (
+                [
+                    0xD5,
+                    (fosc << 4) | ratio.trailing_zeros() as u8,
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                ],
                 2,
             ),
+            Command::ZoomIn(on) => ([0xD6, on as u8, 0, 0, 0, 0, 0, 0], 2),
+            Command::PreChargePeriod(phase1, phase2) => {
+                ([0xD9, (phase2 << 4) | phase1, 0, 0, 0, 0, 0, 0], 2)
+            }
             Command::ComPinConfig(alt, lr) => (
                 [
                     0xDA,
@@ -112,21 +301,271 @@ impl Command {
                     0,
                     0,
                     0,
+                    0,
                 ],
                 2,
             ),
-            Command::VcomhDeselect(level) => ([0xDB, (level as u8) << 4, 0, 0, 0, 0, 0], 2),
-            Command::DisplayLock(lock) => ([0xFD, 0x12 | ((lock as u8) << 2), 0, 0, 0, 0, 0], 2),
+            Command::VcomhDeselect(level) => ([0xDB, (level as u8) << 4, 0, 0, 0, 0, 0, 0], 2),
+            Command::DisplayLock(lock) => ([0xFD, 0x12 | ((lock as u8) << 2), 0, 0, 0, 0, 0, 0], 2),
+            Command::Nop => ([0xE3, 0, 0, 0, 0, 0, 0, 0], 1),
         };
 
         // Send command over the interface
-        iface.send_commands(U8(&data[0..len]))
+        iface.send_commands(U8(&data[0..len])).map_err(Error::Comm)
+    }
+
+    /// Decode the first command of a command byte stream, the inverse of `send`.
+    ///
+    /// Returns the command and the number of its bytes. Numeric values such as addresses,
+    /// rows or the contrast are decoded as they are, even when they are out of the range
+    /// accepted by the controller. Values the command types cannot hold are reported as
+    /// [`Error::Decode`]: bit patterns outside of the enumerated settings, non-zero dummy
+    /// bytes, a VP capacitor setting other than 00 or 11, a clock divide ratio above 256 and
+    /// a gray scale table with decreasing or too long pulse widths.
+    pub fn decode(bytes: &[u8]) -> Result<(Self, usize), Error> {
+        let invalid = Error::Decode(0);
+        let (&opcode, args) = bytes.split_first().ok_or(invalid.clone())?;
+        // Argument of the command with the given index
+        let arg = |index: usize| args.get(index).copied().ok_or(Error::Decode(0));
+
+        let (command, len) = match opcode {
+            0x20 => (Command::AddressMode(AddrMode::from_bits(arg(0)?)?), 2),
+            0x21 => (Command::ColumnAddress(arg(0)?, arg(1)?), 3),
+            0x22 => (Command::RowAddress(arg(0)?, arg(1)?), 3),
+            0x23 => {
+                let value = arg(0)?;
+                let interval = FadeInterval::from_bits(value & 0xF)?;
+                let mode = match value >> 4 {
+                    0b00 if value == 0 => FadeMode::Disabled,
+                    0b10 => FadeMode::FadeOut(interval),
+                    0b11 => FadeMode::Blinking(interval),
+                    _ => return Err(invalid),
+                };
+                (Command::FadeMode(mode), 2)
+            }
+            0x25 => (
+                Command::PortraitAddressMode(PortraitAddrMode::from_bits(arg(0)?)?),
+                2,
+            ),
+            0x26 | 0x27 => {
+                if arg(0)? != 0 || arg(6)? != 0 {
+                    return Err(invalid);
+                }
+                let direction = if opcode & 1 == 0 {
+                    ScrollDirection::Right
+                } else {
+                    ScrollDirection::Left
+                };
+                (
+                    Command::HorizontalScrollSetup(
+                        direction,
+                        arg(1)?,
+                        ScrollInterval::from_bits(arg(2)?)?,
+                        arg(3)?,
+                        arg(4)?,
+                        arg(5)?,
+                    ),
+                    8,
+                )
+            }
+            0x2E | 0x2F => (Command::ScrollActive(opcode & 1 != 0), 1),
+            0x81 => (Command::Contrast(arg(0)?), 2),
+            0xA0 | 0xA1 => (Command::SegmentRemap(opcode & 1 != 0), 1),
+            0xA2 => (Command::StartLine(arg(0)?), 2),
+            0xA4 | 0xA5 => (Command::AllOn(opcode & 1 != 0), 1),
+            0xA6 | 0xA7 => (Command::Invert(opcode & 1 != 0), 1),
+            0xA8 => (Command::Multiplex(arg(0)?.wrapping_add(1)), 2),
+            0xAD => (Command::Iref(Iref::from_bits(arg(0)?)?), 2),
+            0xAE | 0xAF => (Command::DisplayOn(opcode & 1 != 0), 1),
+            0xBC => (
+                Command::PreChargeLevel(PreChargeLvl::from_bits(arg(0)?)?),
+                2,
+            ),
+            0xBD => match arg(0)? {
+                0b00 => (Command::PreChargeCapacitor(false), 2),
+                0b11 => (Command::PreChargeCapacitor(true), 2),
+                _ => return Err(invalid),
+            },
+            0xBE => {
+                let mut widths = [0; 15];
+                widths.copy_from_slice(args.get(..15).ok_or(invalid.clone())?);
+                let table = GrayScaleTable::new(widths).ok_or(invalid)?;
+                (Command::GrayScaleTable(table), 16)
+            }
+            0xBF => (Command::LineralLUT, 1),
+            0xC0 | 0xC8 => (Command::ReverseComDir(opcode & 0x8 != 0), 1),
+            0xD3 => (Command::DisplayOffset(arg(0)?), 2),
+            0xD5 => {
+                let value = arg(0)?;
+                if value & 0xF > 8 {
+                    return Err(invalid);
+                }
+                (Command::DisplayClockDiv(value >> 4, 1 << (value & 0xF)), 2)
+            }
+            0xD6 => match arg(0)? {
+                0 => (Command::ZoomIn(false), 2),
+                1 => (Command::ZoomIn(true), 2),
+                _ => return Err(invalid),
+            },
+            0xD9 => {
+                let value = arg(0)?;
+                (Command::PreChargePeriod(value & 0xF, value >> 4), 2)
+            }
+            0xDA => {
+                let value = arg(0)?;
+                if value & !0x30 != 0x02 {
+                    return Err(invalid);
+                }
+                (
+                    Command::ComPinConfig(value & 0x10 != 0, value & 0x20 != 0),
+                    2,
+                )
+            }
+            0xDB => (
+                Command::VcomhDeselect(VcomhLevel::from_bits(arg(0)? >> 4)?),
+                2,
+            ),
+            0xE3 => (Command::Nop, 1),
+            0xFD => match arg(0)? {
+                0x12 => (Command::DisplayLock(false), 2),
+                0x16 => (Command::DisplayLock(true), 2),
+                _ => return Err(invalid),
+            },
+            _ => return Err(invalid),
+        };
+
+        Ok((command, len))
+    }
+
+    /// Decode a whole command byte stream, for example captured SPI traffic.
+    ///
+    /// The returned [`Commands`] iterator also implements `Display`, which prints one
+    /// command per line together with its offset and bytes.
+    pub fn decode_all(bytes: &[u8]) -> Commands<'_> {
+        Commands { bytes, offset: 0 }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        match *self {
+            Command::AddressMode(mode) => write!(f, "address mode {:?}", mode),
+            Command::ColumnAddress(start, end) => write!(f, "column address {}-{}", start, end),
+            Command::RowAddress(start, end) => write!(f, "row address {}-{}", start, end),
+            Command::PortraitAddressMode(mode) => write!(f, "portrait address mode {:?}", mode),
+            Command::HorizontalScrollSetup(
+                direction,
+                start_row,
+                interval,
+                end_row,
+                start_col,
+                end_col,
+            ) => write!(
+                f,
+                "horizontal scroll {:?} rows {}-{} columns {}-{} every {:?}",
+                direction, start_row, end_row, start_col, end_col, interval
+            ),
+            Command::ScrollActive(active) => write!(f, "scroll {}", on_off(active)),
+            Command::FadeMode(mode) => write!(f, "fade mode {:?}", mode),
+            Command::Contrast(contrast) => write!(f, "contrast 0x{:02X}", contrast),
+            Command::SegmentRemap(remap) => write!(f, "segment remap {}", on_off(remap)),
+            Command::StartLine(line) => write!(f, "start line {}", line),
+            Command::AllOn(on) => write!(f, "entire display {}", on_off(on)),
+            Command::Invert(invert) => write!(f, "invert {}", on_off(invert)),
+            Command::Multiplex(ratio) => write!(f, "multiplex ratio {}", ratio),
+            Command::Iref(iref) => write!(f, "{:?} IREF", iref),
+            Command::DisplayOn(on) => write!(f, "display {}", on_off(on)),
+            Command::PreChargeLevel(level) => write!(f, "pre-charge level {:?}", level),
+            Command::PreChargeCapacitor(on) => write!(f, "VP capacitor {}", on_off(on)),
+            Command::LineralLUT => write!(f, "linear gray scale table"),
+            Command::GrayScaleTable(table) => {
+                write!(f, "gray scale table {:?}", table.widths())
+            }
+            Command::ReverseComDir(reverse) => write!(f, "COM reverse {}", on_off(reverse)),
+            Command::DisplayOffset(offset) => write!(f, "display offset {}", offset),
+            Command::DisplayClockDiv(fosc, ratio) => {
+                write!(f, "clock oscillator {} divide ratio {}", fosc, ratio)
+            }
+            Command::ZoomIn(on) => write!(f, "zoom in {}", on_off(on)),
+            Command::PreChargePeriod(phase1, phase2) => {
+                write!(f, "pre-charge period {} {}", phase1, phase2)
+            }
+            Command::ComPinConfig(alternative, left_right_remap) => write!(
+                f,
+                "COM pins {} left/right remap {}",
+                if alternative {
+                    "alternative"
+                } else {
+                    "sequential"
+                },
+                on_off(left_right_remap)
+            ),
+            Command::VcomhDeselect(level) => write!(f, "Vcomh deselect level {:?}", level),
+            Command::DisplayLock(lock) => write!(f, "command lock {}", on_off(lock)),
+            Command::Nop => write!(f, "nop"),
+        }
+    }
+}
+
+/// Iterator over the commands of a command byte stream, created by [`Command::decode_all`]
+///
+/// Decoding stops after the first command which cannot be decoded, the error contains
+/// the offset of its first byte.
+#[derive(Debug, Clone, Copy)]
+pub struct Commands<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Iterator for Commands<'_> {
+    type Item = Result<Command, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+        match Command::decode(self.bytes) {
+            Ok((command, len)) => {
+                self.bytes = &self.bytes[len..];
+                self.offset += len;
+                Some(Ok(command))
+            }
+            Err(_) => {
+                self.bytes = &[];
+                Some(Err(Error::Decode(self.offset)))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Commands<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut bytes = self.bytes;
+        let mut offset = self.offset;
+        while !bytes.is_empty() {
+            let (command, len) = match Command::decode(bytes) {
+                Ok((command, len)) => (Some(command), len),
+                Err(_) => (None, bytes.len()),
+            };
+            write!(f, "{:4}:", offset)?;
+            for byte in &bytes[..len] {
+                write!(f, " {:02X}", byte)?;
+            }
+            match command {
+                Some(command) => writeln!(f, "  {}", command)?,
+                None => writeln!(f, "  <cannot decode>")?,
+            }
+            bytes = &bytes[len..];
+            offset += len;
+        }
+
+        Ok(())
     }
 }
 
 /// Address mode
 #[derive(Debug, Clone, Copy, PartialEq)]
-#[allow(dead_code)]
 pub enum AddrMode {
     /// Horizontal mode
     Horizontal = 0b00,
@@ -134,9 +573,19 @@ pub enum AddrMode {
     Vertical = 0b01,
 }
 
+impl AddrMode {
+    /// Decode the register bits of the value
+    fn from_bits(bits: u8) -> Result<Self, Error> {
+        [AddrMode::Horizontal, AddrMode::Vertical]
+            .iter()
+            .copied()
+            .find(|value| *value as u8 == bits)
+            .ok_or(Error::Decode(0))
+    }
+}
+
 /// Address mode
 #[derive(Debug, Clone, Copy, PartialEq)]
-#[allow(dead_code)]
 pub enum PortraitAddrMode {
     /// Horizontal mode
     Normal = 0b00,
@@ -144,9 +593,175 @@ pub enum PortraitAddrMode {
     Portrait = 0b01,
 }
 
+impl PortraitAddrMode {
+    /// Decode the register bits of the value
+    fn from_bits(bits: u8) -> Result<Self, Error> {
+        [PortraitAddrMode::Normal, PortraitAddrMode::Portrait]
+            .iter()
+            .copied()
+            .find(|value| *value as u8 == bits)
+            .ok_or(Error::Decode(0))
+    }
+}
+
+/// Horizontal scroll direction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScrollDirection {
+    /// Scroll towards the higher segments
+    Right = 0b0,
+    /// Scroll towards the lower segments
+    Left = 0b1,
+}
+
+impl ScrollDirection {
+    /// The opposite direction
+    pub(crate) fn reverse(self) -> Self {
+        match self {
+            ScrollDirection::Right => ScrollDirection::Left,
+            ScrollDirection::Left => ScrollDirection::Right,
+        }
+    }
+}
+
+/// Time interval between each scroll step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScrollInterval {
+    /// 2 frames
+    Frames2 = 0b111,
+    /// 3 frames
+    Frames3 = 0b100,
+    /// 4 frames
+    Frames4 = 0b101,
+    /// 5 frames
+    Frames5 = 0b110,
+    /// 6 frames
+    Frames6 = 0b000,
+    /// 32 frames
+    Frames32 = 0b001,
+    /// 64 frames
+    Frames64 = 0b010,
+    /// 128 frames
+    Frames128 = 0b011,
+}
+
+impl ScrollInterval {
+    /// Decode the register bits of the value
+    fn from_bits(bits: u8) -> Result<Self, Error> {
+        [
+            ScrollInterval::Frames2,
+            ScrollInterval::Frames3,
+            ScrollInterval::Frames4,
+            ScrollInterval::Frames5,
+            ScrollInterval::Frames6,
+            ScrollInterval::Frames32,
+            ScrollInterval::Frames64,
+            ScrollInterval::Frames128,
+        ]
+        .iter()
+        .copied()
+        .find(|value| *value as u8 == bits)
+        .ok_or(Error::Decode(0))
+    }
+}
+
+/// Fade out and blinking mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FadeMode {
+    /// Disable fade out and blinking
+    Disabled,
+    /// Fade out the display down to zero contrast once, stepping every interval
+    FadeOut(FadeInterval),
+    /// Fade out and fade in the display continuously, stepping every interval
+    Blinking(FadeInterval),
+}
+
+/// Time interval between each fade step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FadeInterval {
+    /// 8 frames
+    Frames8 = 0b0000,
+    /// 16 frames
+    Frames16 = 0b0001,
+    /// 24 frames
+    Frames24 = 0b0010,
+    /// 32 frames
+    Frames32 = 0b0011,
+    /// 40 frames
+    Frames40 = 0b0100,
+    /// 48 frames
+    Frames48 = 0b0101,
+    /// 56 frames
+    Frames56 = 0b0110,
+    /// 64 frames
+    Frames64 = 0b0111,
+    /// 72 frames
+    Frames72 = 0b1000,
+    /// 80 frames
+    Frames80 = 0b1001,
+    /// 88 frames
+    Frames88 = 0b1010,
+    /// 96 frames
+    Frames96 = 0b1011,
+    /// 104 frames
+    Frames104 = 0b1100,
+    /// 112 frames
+    Frames112 = 0b1101,
+    /// 120 frames
+    Frames120 = 0b1110,
+    /// 128 frames
+    Frames128 = 0b1111,
+}
+
+impl FadeInterval {
+    /// Decode the register bits of the value
+    fn from_bits(bits: u8) -> Result<Self, Error> {
+        [
+            FadeInterval::Frames8,
+            FadeInterval::Frames16,
+            FadeInterval::Frames24,
+            FadeInterval::Frames32,
+            FadeInterval::Frames40,
+            FadeInterval::Frames48,
+            FadeInterval::Frames56,
+            FadeInterval::Frames64,
+            FadeInterval::Frames72,
+            FadeInterval::Frames80,
+            FadeInterval::Frames88,
+            FadeInterval::Frames96,
+            FadeInterval::Frames104,
+            FadeInterval::Frames112,
+            FadeInterval::Frames120,
+            FadeInterval::Frames128,
+        ]
+        .iter()
+        .copied()
+        .find(|value| *value as u8 == bits)
+        .ok_or(Error::Decode(0))
+    }
+}
+
+/// I REF source
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Iref {
+    /// External I REF resistor
+    External = 0b0_0000,
+    /// Internal I REF, enabled while the display is on
+    Internal = 0b1_0000,
+}
+
+impl Iref {
+    /// Decode the register bits of the value
+    fn from_bits(bits: u8) -> Result<Self, Error> {
+        [Iref::External, Iref::Internal]
+            .iter()
+            .copied()
+            .find(|value| *value as u8 == bits)
+            .ok_or(Error::Decode(0))
+    }
+}
+
 /// Pre-charge level
 #[derive(Debug, Clone, Copy, PartialEq)]
-#[allow(dead_code)]
 pub enum PreChargeLvl {
     /// 0.10 * Vcc
     V010 = 0b00000,
@@ -156,9 +771,19 @@ pub enum PreChargeLvl {
     V05133 = 0b11111,
 }
 
+impl PreChargeLvl {
+    /// Decode the register bits of the value
+    fn from_bits(bits: u8) -> Result<Self, Error> {
+        [PreChargeLvl::V010, PreChargeLvl::V050, PreChargeLvl::V05133]
+            .iter()
+            .copied()
+            .find(|value| *value as u8 == bits)
+            .ok_or(Error::Decode(0))
+    }
+}
+
 /// Vcomh Deselect level
-#[derive(Debug, Clone, Copy)]
-#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum VcomhLevel {
     /// 0.72 * Vcc
     V072 = 0b000,
@@ -169,3 +794,375 @@ pub enum VcomhLevel {
     /// 0.84 * Vcc
     V084 = 0b110,
 }
+
+impl VcomhLevel {
+    /// Decode the register bits of the value
+    fn from_bits(bits: u8) -> Result<Self, Error> {
+        [
+            VcomhLevel::V072,
+            VcomhLevel::V076,
+            VcomhLevel::V080,
+            VcomhLevel::V084,
+        ]
+        .iter()
+        .copied()
+        .find(|value| *value as u8 == bits)
+        .ok_or(Error::Decode(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    use std::string::ToString;
+
+    use display_interface::{DataFormat, DisplayError};
+
+    /// Interface keeping the bytes of the sent commands
+    struct Recorder {
+        data: [u8; 16],
+        len: usize,
+    }
+
+    impl WriteOnlyDataCommand for Recorder {
+        fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+            match cmd {
+                DataFormat::U8(bytes) => {
+                    self.data[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+                    self.len += bytes.len();
+                    Ok(())
+                }
+                _ => Err(DisplayError::DataFormatNotImplemented),
+            }
+        }
+
+        fn send_data(&mut self, _buf: DataFormat<'_>) -> Result<(), DisplayError> {
+            Err(DisplayError::DataFormatNotImplemented)
+        }
+    }
+
+    fn assert_bytes(command: Command, expected: &[u8]) {
+        let mut recorder = Recorder {
+            data: [0; 16],
+            len: 0,
+        };
+        command.send(&mut recorder).unwrap();
+        assert_eq!(&recorder.data[..recorder.len], expected, "{:?}", command);
+        assert_eq!(
+            Command::decode(expected).unwrap(),
+            (command, expected.len())
+        );
+    }
+
+    fn assert_invalid(command: Result<Command, Error>) {
+        assert!(matches!(command, Err(Error::InvalidParameter(_))));
+    }
+
+    #[test]
+    fn address_mode() {
+        assert_bytes(Command::AddressMode(AddrMode::Horizontal), &[0x20, 0x00]);
+        assert_bytes(Command::AddressMode(AddrMode::Vertical), &[0x20, 0x01]);
+    }
+
+    #[test]
+    fn column_address() {
+        assert_bytes(Command::column_address(0, 79).unwrap(), &[0x21, 0, 79]);
+        assert_invalid(Command::column_address(0, 80));
+    }
+
+    #[test]
+    fn row_address() {
+        assert_bytes(Command::row_address(10, 159).unwrap(), &[0x22, 10, 159]);
+        assert_invalid(Command::row_address(160, 0));
+    }
+
+    #[test]
+    fn portrait_address_mode() {
+        assert_bytes(
+            Command::PortraitAddressMode(PortraitAddrMode::Normal),
+            &[0x25, 0x00],
+        );
+        assert_bytes(
+            Command::PortraitAddressMode(PortraitAddrMode::Portrait),
+            &[0x25, 0x01],
+        );
+    }
+
+    #[test]
+    fn horizontal_scroll_setup() {
+        let command = Command::horizontal_scroll_setup(
+            ScrollDirection::Right,
+            2,
+            ScrollInterval::Frames5,
+            131,
+            4,
+            75,
+        );
+        assert_bytes(command.unwrap(), &[0x26, 0x00, 2, 0b110, 131, 4, 75, 0x00]);
+        let command = Command::horizontal_scroll_setup(
+            ScrollDirection::Left,
+            0,
+            ScrollInterval::Frames2,
+            159,
+            0,
+            79,
+        );
+        assert_bytes(command.unwrap(), &[0x27, 0x00, 0, 0b111, 159, 0, 79, 0x00]);
+        assert_invalid(Command::horizontal_scroll_setup(
+            ScrollDirection::Left,
+            10,
+            ScrollInterval::Frames2,
+            9,
+            0,
+            79,
+        ));
+    }
+
+    #[test]
+    fn scroll_active() {
+        assert_bytes(Command::ScrollActive(false), &[0x2E]);
+        assert_bytes(Command::ScrollActive(true), &[0x2F]);
+    }
+
+    #[test]
+    fn fade_mode() {
+        assert_bytes(Command::FadeMode(FadeMode::Disabled), &[0x23, 0x00]);
+        assert_bytes(
+            Command::FadeMode(FadeMode::FadeOut(FadeInterval::Frames16)),
+            &[0x23, 0x21],
+        );
+        assert_bytes(
+            Command::FadeMode(FadeMode::Blinking(FadeInterval::Frames128)),
+            &[0x23, 0x3F],
+        );
+    }
+
+    #[test]
+    fn contrast() {
+        assert_bytes(Command::Contrast(0x9F), &[0x81, 0x9F]);
+    }
+
+    #[test]
+    fn segment_remap() {
+        assert_bytes(Command::SegmentRemap(false), &[0xA0]);
+        assert_bytes(Command::SegmentRemap(true), &[0xA1]);
+    }
+
+    #[test]
+    fn start_line() {
+        assert_bytes(Command::start_line(159).unwrap(), &[0xA2, 159]);
+        assert_invalid(Command::start_line(160));
+    }
+
+    #[test]
+    fn all_on() {
+        assert_bytes(Command::AllOn(false), &[0xA4]);
+        assert_bytes(Command::AllOn(true), &[0xA5]);
+    }
+
+    #[test]
+    fn invert() {
+        assert_bytes(Command::Invert(false), &[0xA6]);
+        assert_bytes(Command::Invert(true), &[0xA7]);
+    }
+
+    #[test]
+    fn multiplex() {
+        assert_bytes(Command::multiplex(132).unwrap(), &[0xA8, 131]);
+        assert_bytes(Command::multiplex(16).unwrap(), &[0xA8, 15]);
+        assert_invalid(Command::multiplex(15));
+        assert_invalid(Command::multiplex(161));
+        assert_eq!(Command::multiplex(132).unwrap(), Command::Multiplex(132));
+    }
+
+    #[test]
+    fn iref() {
+        assert_bytes(Command::Iref(Iref::External), &[0xAD, 0x00]);
+        assert_bytes(Command::Iref(Iref::Internal), &[0xAD, 0x10]);
+    }
+
+    #[test]
+    fn display_on() {
+        assert_bytes(Command::DisplayOn(false), &[0xAE]);
+        assert_bytes(Command::DisplayOn(true), &[0xAF]);
+    }
+
+    #[test]
+    fn precharge_level() {
+        assert_bytes(Command::PreChargeLevel(PreChargeLvl::V010), &[0xBC, 0x00]);
+        assert_bytes(Command::PreChargeLevel(PreChargeLvl::V050), &[0xBC, 0x1E]);
+        assert_bytes(Command::PreChargeLevel(PreChargeLvl::V05133), &[0xBC, 0x1F]);
+    }
+
+    #[test]
+    fn precharge_capacitor() {
+        assert_bytes(Command::PreChargeCapacitor(false), &[0xBD, 0x00]);
+        assert_bytes(Command::PreChargeCapacitor(true), &[0xBD, 0x03]);
+    }
+
+    #[test]
+    fn linear_gray_scale_table() {
+        assert_bytes(Command::LineralLUT, &[0xBF]);
+    }
+
+    #[test]
+    fn gray_scale_table() {
+        let mut expected = [0xBE; 16];
+        expected[1..].copy_from_slice(GrayScaleTable::GAMMA_2_2.widths());
+        assert_bytes(
+            Command::GrayScaleTable(GrayScaleTable::GAMMA_2_2),
+            &expected,
+        );
+    }
+
+    #[test]
+    fn reverse_com_dir() {
+        assert_bytes(Command::ReverseComDir(false), &[0xC0]);
+        assert_bytes(Command::ReverseComDir(true), &[0xC8]);
+    }
+
+    #[test]
+    fn display_offset() {
+        assert_bytes(Command::display_offset(0x0E).unwrap(), &[0xD3, 0x0E]);
+        assert_invalid(Command::display_offset(160));
+    }
+
+    #[test]
+    fn display_clock_div() {
+        assert_bytes(Command::display_clock_div(0x7, 4).unwrap(), &[0xD5, 0x72]);
+        assert_bytes(Command::display_clock_div(0xF, 256).unwrap(), &[0xD5, 0xF8]);
+        assert_bytes(Command::display_clock_div(0, 1).unwrap(), &[0xD5, 0x00]);
+        assert_invalid(Command::display_clock_div(0x10, 1));
+        assert_invalid(Command::display_clock_div(0, 3));
+        assert_invalid(Command::display_clock_div(0, 512));
+        assert_invalid(Command::display_clock_div(0, 0));
+    }
+
+    #[test]
+    fn zoom_in() {
+        assert_bytes(Command::ZoomIn(false), &[0xD6, 0x00]);
+        assert_bytes(Command::ZoomIn(true), &[0xD6, 0x01]);
+    }
+
+    #[test]
+    fn precharge_period() {
+        assert_bytes(Command::precharge_period(0xA, 0x1).unwrap(), &[0xD9, 0x1A]);
+        assert_bytes(Command::precharge_period(0xA, 0x0).unwrap(), &[0xD9, 0x0A]);
+        assert_invalid(Command::precharge_period(0, 1));
+        assert_invalid(Command::precharge_period(1, 16));
+    }
+
+    #[test]
+    fn com_pin_config() {
+        assert_bytes(Command::ComPinConfig(false, false), &[0xDA, 0x02]);
+        assert_bytes(Command::ComPinConfig(true, false), &[0xDA, 0x12]);
+        assert_bytes(Command::ComPinConfig(false, true), &[0xDA, 0x22]);
+    }
+
+    #[test]
+    fn vcomh_deselect() {
+        assert_bytes(Command::VcomhDeselect(VcomhLevel::V072), &[0xDB, 0x00]);
+        assert_bytes(Command::VcomhDeselect(VcomhLevel::V084), &[0xDB, 0x60]);
+    }
+
+    #[test]
+    fn display_lock() {
+        assert_bytes(Command::DisplayLock(false), &[0xFD, 0x12]);
+        assert_bytes(Command::DisplayLock(true), &[0xFD, 0x16]);
+    }
+
+    #[test]
+    fn nop() {
+        assert_bytes(Command::Nop, &[0xE3]);
+    }
+
+    #[test]
+    fn unchecked_variant_is_rejected_on_send() {
+        let mut recorder = Recorder {
+            data: [0; 16],
+            len: 0,
+        };
+        let result = Command::StartLine(200).send(&mut recorder);
+        assert!(matches!(result, Err(Error::InvalidParameter("start line"))));
+        assert_eq!(recorder.len, 0);
+    }
+
+    #[test]
+    fn decode_stream() {
+        let bytes = [0xFD, 0x12, 0xAE, 0xA8, 0x83, 0x21, 0x00, 0x4F, 0xAF];
+        let mut commands = Command::decode_all(&bytes);
+        assert_eq!(
+            commands.next().unwrap().unwrap(),
+            Command::DisplayLock(false)
+        );
+        assert_eq!(commands.next().unwrap().unwrap(), Command::DisplayOn(false));
+        assert_eq!(commands.next().unwrap().unwrap(), Command::Multiplex(132));
+        assert_eq!(
+            commands.next().unwrap().unwrap(),
+            Command::ColumnAddress(0, 79)
+        );
+        assert_eq!(commands.next().unwrap().unwrap(), Command::DisplayOn(true));
+        assert!(commands.next().is_none());
+    }
+
+    #[test]
+    fn decode_stops_at_invalid_command() {
+        let bytes = [0xAE, 0x00, 0xAF];
+        let mut commands = Command::decode_all(&bytes);
+        assert_eq!(commands.next().unwrap().unwrap(), Command::DisplayOn(false));
+        assert!(matches!(commands.next(), Some(Err(Error::Decode(1)))));
+        assert!(commands.next().is_none());
+    }
+
+    #[test]
+    fn decode_out_of_range_values_as_they_are() {
+        assert_eq!(
+            Command::decode(&[0x22, 200, 0]).unwrap(),
+            (Command::RowAddress(200, 0), 3)
+        );
+        assert_eq!(
+            Command::decode(&[0xD9, 0x10]).unwrap(),
+            (Command::PreChargePeriod(0, 1), 2)
+        );
+    }
+
+    #[test]
+    fn decode_values_without_a_command_type() {
+        let mut table = [0xBE; 16];
+        table[1..].copy_from_slice(GrayScaleTable::LINEAR.widths());
+        table[2] = 0;
+        assert!(matches!(Command::decode(&table), Err(Error::Decode(0))));
+        assert!(matches!(
+            Command::decode(&[0xBD, 0x01]),
+            Err(Error::Decode(0))
+        ));
+        assert!(matches!(
+            Command::decode(&[0xD5, 0x09]),
+            Err(Error::Decode(0))
+        ));
+    }
+
+    #[test]
+    fn decode_truncated_command() {
+        assert!(matches!(
+            Command::decode(&[0x21, 0x00]),
+            Err(Error::Decode(0))
+        ));
+        assert!(matches!(Command::decode(&[]), Err(Error::Decode(0))));
+    }
+
+    #[test]
+    fn display_stream() {
+        let bytes = [0xFD, 0x12, 0xA8, 0x83, 0xD5, 0x72, 0x01];
+        assert_eq!(
+            Command::decode_all(&bytes).to_string(),
+            "   0: FD 12  command lock off\n\
+             \x20  2: A8 83  multiplex ratio 132\n\
+             \x20  4: D5 72  clock oscillator 7 divide ratio 4\n\
+             \x20  6: 01  <cannot decode>\n"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Display initialisation configuration.
+
+pub use crate::command::{AddrMode, Iref, PortraitAddrMode, PreChargeLvl, VcomhLevel};
+pub use crate::grayscale::GrayScaleTable;
+
+/// Register values sent to the controller by `init`
+///
+/// The defaults match the values used by the Surenoo SUR383S1000WG01 module.
+/// Every setter consumes the configuration and returns the updated one, so
+/// settings can be chained:
+///
+/// ```
+/// use ssd1320::config::{Config, VcomhLevel};
+///
+/// let config = Config::new().contrast(0x9f).vcomh_deselect(VcomhLevel::V084);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct Config {
+    pub(crate) clock_div: (u8, u16),
+    pub(crate) multiplex: Option<u8>,
+    pub(crate) display_offset: Option<u8>,
+    pub(crate) address_mode: AddrMode,
+    pub(crate) portrait_address_mode: PortraitAddrMode,
+    pub(crate) start_line: u8,
+    pub(crate) contrast: u8,
+    pub(crate) segment_remap: bool,
+    pub(crate) all_on: bool,
+    pub(crate) invert: bool,
+    pub(crate) iref: Iref,
+    pub(crate) precharge_level: PreChargeLvl,
+    pub(crate) precharge_capacitor: bool,
+    pub(crate) gray_scale_table: Option<GrayScaleTable>,
+    pub(crate) com_reverse: bool,
+    pub(crate) precharge_period: (u8, u8),
+    pub(crate) com_pin_config: (bool, bool),
+    pub(crate) vcomh_deselect: VcomhLevel,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            clock_div: (0x7, 4),
+            multiplex: None,
+            display_offset: None,
+            address_mode: AddrMode::Horizontal,
+            portrait_address_mode: PortraitAddrMode::Normal,
+            start_line: 0,
+            contrast: 0x70,
+            segment_remap: false,
+            all_on: false,
+            invert: false,
+            iref: Iref::Internal,
+            precharge_level: PreChargeLvl::V050,
+            precharge_capacitor: true,
+            gray_scale_table: None,
+            com_reverse: false,
+            precharge_period: (0xa, 0x0),
+            com_pin_config: (true, false),
+            vcomh_deselect: VcomhLevel::V080,
+        }
+    }
+}
+
+impl Config {
+    /// Create a configuration with the default register values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set up display clock.
+    /// First value is oscillator frequency (0-15), increasing with higher value.
+    /// Second value is divide ratio (1, 2, 4, 8 ... 256).
+    pub fn clock_div(mut self, fosc: u8, ratio: u16) -> Self {
+        self.clock_div = (fosc, ratio);
+        self
+    }
+
+    /// Set multiplex ratio from 16-160, `ratio` lines are scanned, defaults to the display
+    /// height
+    pub fn multiplex(mut self, ratio: u8) -> Self {
+        self.multiplex = Some(ratio);
+        self
+    }
+
+    /// Set vertical shift by COM, defaults to the offset of the display size
+    pub fn display_offset(mut self, offset: u8) -> Self {
+        self.display_offset = Some(offset);
+        self
+    }
+
+    /// Set addressing mode
+    pub fn address_mode(mut self, mode: AddrMode) -> Self {
+        self.address_mode = mode;
+        self
+    }
+
+    /// Set portrait addressing mode
+    pub fn portrait_address_mode(mut self, mode: PortraitAddrMode) -> Self {
+        self.portrait_address_mode = mode;
+        self
+    }
+
+    /// Set display start line
+    pub fn start_line(mut self, line: u8) -> Self {
+        self.start_line = line;
+        self
+    }
+
+    /// Set contrast. Higher number is higher contrast
+    pub fn contrast(mut self, contrast: u8) -> Self {
+        self.contrast = contrast;
+        self
+    }
+
+    /// Reverse columns from 79-0
+    pub fn segment_remap(mut self, remap: bool) -> Self {
+        self.segment_remap = remap;
+        self
+    }
+
+    /// Turn entire display on regardless of the display RAM content
+    pub fn all_on(mut self, on: bool) -> Self {
+        self.all_on = on;
+        self
+    }
+
+    /// Invert display
+    pub fn invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    /// Select internal or external I REF
+    pub fn iref(mut self, iref: Iref) -> Self {
+        self.iref = iref;
+        self
+    }
+
+    /// Set pre-charge voltage level
+    pub fn precharge_level(mut self, level: PreChargeLvl) -> Self {
+        self.precharge_level = level;
+        self
+    }
+
+    /// Enable or disable the external capacitor on the VP pin
+    pub fn precharge_capacitor(mut self, on: bool) -> Self {
+        self.precharge_capacitor = on;
+        self
+    }
+
+    /// Set user-defined gray scale table instead of the built-in linear one
+    pub fn gray_scale_table(mut self, table: GrayScaleTable) -> Self {
+        self.gray_scale_table = Some(table);
+        self
+    }
+
+    /// Scan from COM[n-1] to COM0 (where N is mux ratio)
+    pub fn com_reverse(mut self, reverse: bool) -> Self {
+        self.com_reverse = reverse;
+        self
+    }
+
+    /// Set up phase 1 (1-15 DCLKs) and phase 2 (0-15 DCLKs) of precharge period
+    pub fn precharge_period(mut self, phase1: u8, phase2: u8) -> Self {
+        self.precharge_period = (phase1, phase2);
+        self
+    }
+
+    /// Setup com hardware configuration.
+    /// First value selects sequential (false) or alternative (true) pin configuration,
+    /// second value disables (false) or enables (true) left/right remap.
+    pub fn com_pin_config(mut self, alternative: bool, left_right_remap: bool) -> Self {
+        self.com_pin_config = (alternative, left_right_remap);
+        self
+    }
+
+    /// Set Vcomh deselect level
+    pub fn vcomh_deselect(mut self, level: VcomhLevel) -> Self {
+        self.vcomh_deselect = level;
+        self
+    }
+
+    /// Copy of the configuration with the orientation settings of one controller
+    pub(crate) fn with_orientation(mut self, offset: u8, remap: bool, com_reverse: bool) -> Self {
+        self.display_offset = Some(offset);
+        self.segment_remap = remap;
+        self.com_reverse = com_reverse;
+        self
+    }
+}
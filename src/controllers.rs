@@ -0,0 +1,277 @@
+//! Pair of SSD1320 controllers sharing one interface.
+
+use core::cmp::min;
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::{
+    consts::ssd1320,
+    error::Error,
+    rotation::DisplayRotation,
+    size::{DisplaySize, DualDisplaySize},
+    Ssd1320,
+};
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Frame {
+    pub start: (u16, u16),
+    pub end: (u16, u16),
+}
+
+impl Frame {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            start: (0, 0),
+            end: (width - 1, height - 1),
+        }
+    }
+
+    /// Check that the frame is not empty and lies within `width` x `height` pixels
+    pub fn is_within(&self, width: u16, height: u16) -> bool {
+        self.start.0 <= self.end.0
+            && self.start.1 <= self.end.1
+            && self.end.0 < width
+            && self.end.1 < height
+    }
+
+    pub fn normalize(&self, half_width: u16) -> Self {
+        Self {
+            start: (self.start.0 % half_width, self.start.1),
+            end: (self.end.0 % half_width, self.end.1),
+        }
+    }
+
+    pub fn split_to_two(&self, half_width: u16) -> (Self, Self) {
+        let start_x = self.start.0;
+        let end_x = self.end.0;
+
+        (
+            Self {
+                start: (start_x, self.start.1),
+                end: (half_width - 1, self.end.1),
+            },
+            Self {
+                start: (0, self.start.1),
+                end: (end_x, self.end.1),
+            },
+        )
+    }
+
+    pub fn as_u8(&self) -> ((u8, u8), (u8, u8)) {
+        (
+            (self.start.0 as u8, self.start.1 as u8),
+            (self.end.0 as u8, self.end.1 as u8),
+        )
+    }
+}
+
+/// Two controllers selected by their own chip select pins, the window of the last
+/// `set_draw_area` call and the position of the next `draw` call inside of its row in
+/// display RAM columns
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Controllers<DI, CS1, CS2, SIZE>
+where
+    SIZE: DualDisplaySize,
+{
+    pub interface: Ssd1320<DI, SIZE::Half>,
+    pub rotation: DisplayRotation,
+    frame: Frame,
+    position: u16,
+    cs1: CS1,
+    cs2: CS2,
+}
+
+impl<DI, CS1, CS2, SIZE> Controllers<DI, CS1, CS2, SIZE>
+where
+    SIZE: DualDisplaySize,
+{
+    pub fn new(interface: DI, cs1: CS1, cs2: CS2, rotation: DisplayRotation) -> Self {
+        Self {
+            interface: Ssd1320::new(interface, SIZE::Half::default()),
+            rotation,
+            frame: Frame::new(SIZE::WIDTH, SIZE::HEIGHT),
+            position: 0,
+            cs1,
+            cs2,
+        }
+    }
+
+    pub fn release(self) -> (DI, CS1, CS2) {
+        (self.interface.release(), self.cs1, self.cs2)
+    }
+}
+
+impl<DI, CS1, CS2, SIZE> Controllers<DI, CS1, CS2, SIZE>
+where
+    DI: WriteOnlyDataCommand,
+    CS1: OutputPin,
+    CS2: OutputPin<Error = CS1::Error>,
+    SIZE: DualDisplaySize,
+{
+    pub fn select_one(&mut self) -> Result<(), Error<CS1::Error>> {
+        self.cs2.set_high().map_err(Error::ChipSelect)?;
+        self.cs1.set_low().map_err(Error::ChipSelect)?;
+
+        Ok(())
+    }
+
+    pub fn select_two(&mut self) -> Result<(), Error<CS1::Error>> {
+        self.cs1.set_high().map_err(Error::ChipSelect)?;
+        self.cs2.set_low().map_err(Error::ChipSelect)?;
+
+        Ok(())
+    }
+
+    /// Select the controller driving the left half of the image
+    pub fn select_left(&mut self) -> Result<(), Error<CS1::Error>> {
+        if self.rotation.is_flipped() {
+            self.select_two()
+        } else {
+            self.select_one()
+        }
+    }
+
+    /// Select the controller driving the right half of the image
+    pub fn select_right(&mut self) -> Result<(), Error<CS1::Error>> {
+        if self.rotation.is_flipped() {
+            self.select_one()
+        } else {
+            self.select_two()
+        }
+    }
+
+    pub fn select_all(&mut self) -> Result<(), Error<CS1::Error>> {
+        self.cs1.set_low().map_err(Error::ChipSelect)?;
+        self.cs2.set_low().map_err(Error::ChipSelect)?;
+
+        Ok(())
+    }
+
+    pub fn unselect_all(&mut self) -> Result<(), Error<CS1::Error>> {
+        self.cs1.set_high().map_err(Error::ChipSelect)?;
+        self.cs2.set_high().map_err(Error::ChipSelect)?;
+
+        Ok(())
+    }
+
+    /// Segment remap and COM scan direction of the first controller, the second controller
+    /// is mounted upside down, so it uses the opposite values.
+    pub fn orientation(&self) -> (bool, bool) {
+        if self.rotation.is_flipped() {
+            (true, false)
+        } else {
+            (false, true)
+        }
+    }
+
+    /// COM lines of a controller scanning in normal direction are shifted the opposite way.
+    pub fn display_offset(com_reverse: bool) -> u8 {
+        if com_reverse {
+            SIZE::OFFSETY
+        } else {
+            ((ssd1320::NUM_PIXELS_ROWS - SIZE::OFFSETY as u16) % ssd1320::NUM_PIXELS_ROWS) as u8
+        }
+    }
+
+    pub fn set_orientation(
+        &mut self,
+        remap: bool,
+        com_reverse: bool,
+    ) -> Result<(), Error<CS1::Error>> {
+        self.interface
+            .set_segment_remap(remap)
+            .map_err(Error::with_pin_error)?;
+        self.interface
+            .set_com_reverse(com_reverse)
+            .map_err(Error::with_pin_error)?;
+        self.interface
+            .set_display_offset(Self::display_offset(com_reverse))
+            .map_err(Error::with_pin_error)
+    }
+
+    /// Set the area of the display in the landscape orientation where any sent data
+    /// should be drawn.
+    pub fn set_draw_area(
+        &mut self,
+        start: (u16, u16),
+        end: (u16, u16),
+    ) -> Result<(), Error<CS1::Error>> {
+        let half_width = SIZE::Half::WIDTH;
+        let frame = Frame { start, end };
+        if !frame.is_within(SIZE::WIDTH, SIZE::HEIGHT) {
+            return Err(Error::InvalidParameter("draw area"));
+        }
+        self.frame = frame;
+        self.position = 0;
+        if start.0 < half_width && end.0 >= half_width {
+            let (one, two) = self.frame.split_to_two(half_width);
+            let one = one.normalize(half_width).as_u8();
+            let two = two.normalize(half_width).as_u8();
+            self.select_left()?;
+            self.interface
+                .set_draw_area(one.0, one.1)
+                .map_err(Error::with_pin_error)?;
+            self.select_right()?;
+            self.interface
+                .set_draw_area(two.0, two.1)
+                .map_err(Error::with_pin_error)?;
+        } else {
+            if start.0 < half_width {
+                self.select_left()?;
+            } else {
+                self.select_right()?;
+            }
+            let adopted_frame = self.frame.normalize(half_width).as_u8();
+            self.interface
+                .set_draw_area(adopted_frame.0, adopted_frame.1)
+                .map_err(Error::with_pin_error)?;
+        }
+        self.unselect_all()?;
+
+        Ok(())
+    }
+
+    /// Send a raw buffer to the selected draw area, splitting it between the controllers.
+    ///
+    /// Every byte fills a display RAM column of two pixels, so a window with odd edges
+    /// takes whole columns on each controller.
+    pub fn draw(&mut self, buffer: &[u8]) -> Result<(), Error<CS1::Error>> {
+        let half_width = SIZE::Half::WIDTH;
+        let Frame { start, end } = self.frame;
+        if start.0 < half_width && end.0 >= half_width {
+            // Columns of a window row on the left controller and on both of them
+            let left_len = (half_width / 2 - start.0 / 2) as usize;
+            let row_len = left_len + (end.0 / 2 - half_width / 2 + 1) as usize;
+            let mut index = 0;
+
+            while index < buffer.len() {
+                let advance = if (self.position as usize) < left_len {
+                    self.select_left()?;
+
+                    left_len - self.position as usize
+                } else {
+                    self.select_right()?;
+
+                    row_len - self.position as usize
+                };
+                let end_index = min(index + advance, buffer.len());
+                self.interface
+                    .draw(&buffer[index..end_index])
+                    .map_err(Error::with_pin_error)?;
+                self.position = ((self.position as usize + end_index - index) % row_len) as u16;
+                index = end_index;
+            }
+        } else {
+            if start.0 < half_width {
+                self.select_left()?;
+            } else {
+                self.select_right()?;
+            }
+            self.interface.draw(buffer).map_err(Error::with_pin_error)?;
+        }
+        self.unselect_all()?;
+
+        Ok(())
+    }
+}
@@ -2,92 +2,202 @@
 //!
 //! TODO: Create a normal documentation
 
-use crate::command::{AddrMode, Command, PortraitAddrMode, PreChargeLvl, VcomhLevel};
+use crate::command::{Command, FadeMode, ScrollDirection, ScrollInterval};
+use crate::config::Config;
 use crate::error::Error;
+use crate::grayscale::GrayScaleTable;
+use crate::size::{DisplaySize, DisplaySize160x160};
 
-use display_interface::{DataFormat::U8, DisplayError, WriteOnlyDataCommand};
-use display_interface_spi::SPIInterfaceNoCS;
+use core::marker::PhantomData;
+
+use display_interface::{DataFormat::U8, WriteOnlyDataCommand};
 use embedded_hal::{blocking::delay::DelayMs, digital::v2::OutputPin};
 
 /// SSD1320 driver.
 #[derive(Copy, Clone, Debug)]
-pub struct Ssd1320<DI> {
+pub struct Ssd1320<DI, SIZE = DisplaySize160x160> {
     interface: DI,
+    size: PhantomData<SIZE>,
 }
 
-impl<DI> Ssd1320<DI>
+impl<DI, SIZE> Ssd1320<DI, SIZE>
 where
-    DI: WriteOnlyDataCommand,
+    SIZE: DisplaySize,
 {
     /// Create a SSD1320 interface
-    pub fn new(interface: DI) -> Self {
-        Self { interface }
+    pub fn new(interface: DI, _size: SIZE) -> Self {
+        Self {
+            interface,
+            size: PhantomData,
+        }
     }
 
-    /// Initialise the display in one of the available addressing modes.
-    /// TODO: Add address setup
-    pub fn init(
-        &mut self,
-        display_offset: u8,
-        regmap: bool,
-        com_reverse: bool,
-    ) -> Result<(), DisplayError> {
+    /// Release the interface.
+    pub fn release(self) -> DI {
+        self.interface
+    }
+}
+
+impl<DI, SIZE> Ssd1320<DI, SIZE>
+where
+    DI: WriteOnlyDataCommand,
+    SIZE: DisplaySize,
+{
+    /// Initialise the display with the register values of the configuration.
+    pub fn init(&mut self, config: &Config) -> Result<(), Error> {
+        let (fosc, ratio) = config.clock_div;
+        let (phase1, phase2) = config.precharge_period;
+        let (alternative, left_right_remap) = config.com_pin_config;
+        let multiplex = config.multiplex.unwrap_or(SIZE::HEIGHT as u8);
+        let display_offset = config.display_offset.unwrap_or(SIZE::OFFSETY);
+
         Command::DisplayLock(false).send(&mut self.interface)?;
         Command::DisplayOn(false).send(&mut self.interface)?;
-        Command::DisplayClockDiv(0x7, 0x2).send(&mut self.interface)?;
-        Command::Multiplex(0x83).send(&mut self.interface)?;
+        Command::DisplayClockDiv(fosc, ratio).send(&mut self.interface)?;
+        Command::Multiplex(multiplex).send(&mut self.interface)?;
         Command::DisplayOffset(display_offset).send(&mut self.interface)?;
-        Command::AddressMode(AddrMode::Horizontal).send(&mut self.interface)?;
-        Command::PortraitAddressMode(PortraitAddrMode::Normal).send(&mut self.interface)?;
-        Command::StartLine(0).send(&mut self.interface)?;
-        Command::Contrast(0x70).send(&mut self.interface)?;
-        Command::SegmentRemap(regmap).send(&mut self.interface)?;
-        Command::AllOn(false).send(&mut self.interface)?;
-        Command::Invert(false).send(&mut self.interface)?;
-        Command::InternalIref(true).send(&mut self.interface)?;
-        Command::PreChargeLevel(PreChargeLvl::V050).send(&mut self.interface)?;
-        Command::LineralLUT.send(&mut self.interface)?;
-        Command::ReverseComDir(com_reverse).send(&mut self.interface)?;
-        Command::PreChargePeriod(0xa, 0x0).send(&mut self.interface)?;
-        Command::ComPinConfig(true, false).send(&mut self.interface)?;
-        Command::VP.send(&mut self.interface)?;
-        Command::VcomhDeselect(VcomhLevel::V080).send(&mut self.interface)?;
+        Command::AddressMode(config.address_mode).send(&mut self.interface)?;
+        Command::PortraitAddressMode(config.portrait_address_mode).send(&mut self.interface)?;
+        Command::StartLine(config.start_line).send(&mut self.interface)?;
+        Command::Contrast(config.contrast).send(&mut self.interface)?;
+        Command::SegmentRemap(config.segment_remap).send(&mut self.interface)?;
+        Command::AllOn(config.all_on).send(&mut self.interface)?;
+        Command::Invert(config.invert).send(&mut self.interface)?;
+        Command::Iref(config.iref).send(&mut self.interface)?;
+        Command::PreChargeLevel(config.precharge_level).send(&mut self.interface)?;
+        match config.gray_scale_table {
+            Some(table) => Command::GrayScaleTable(table).send(&mut self.interface)?,
+            None => Command::LineralLUT.send(&mut self.interface)?,
+        }
+        Command::ReverseComDir(config.com_reverse).send(&mut self.interface)?;
+        Command::PreChargePeriod(phase1, phase2).send(&mut self.interface)?;
+        Command::ComPinConfig(alternative, left_right_remap).send(&mut self.interface)?;
+        Command::PreChargeCapacitor(config.precharge_capacitor).send(&mut self.interface)?;
+        Command::VcomhDeselect(config.vcomh_deselect).send(&mut self.interface)?;
         Command::DisplayOn(true).send(&mut self.interface)?;
         Ok(())
     }
 
+    /// Send a single command to the display.
+    ///
+    /// The command is sent as is, so it can change registers the driver relies on.
+    pub fn send_command(&mut self, command: Command) -> Result<(), Error> {
+        command.send(&mut self.interface)
+    }
+
     /// Send a raw buffer to the display.
-    pub fn draw(&mut self, buffer: &[u8]) -> Result<(), DisplayError> {
-        self.interface.send_data(U8(&buffer))
+    pub fn draw(&mut self, buffer: &[u8]) -> Result<(), Error> {
+        self.interface.send_data(U8(buffer)).map_err(Error::Comm)
     }
 
     /// Turn the display on or off. The display can be drawn to and retains all
     /// of its memory even while off.
-    pub fn set_display_on(&mut self, on: bool) -> Result<(), DisplayError> {
+    pub fn set_display_on(&mut self, on: bool) -> Result<(), Error> {
         Command::DisplayOn(on).send(&mut self.interface)
     }
 
+    /// Set display start line from 0-159, the display RAM row shown on the first line.
+    /// Changing it scrolls the whole display vertically, wrapping around the 160 rows
+    /// of the display RAM.
+    pub fn set_start_line(&mut self, line: u8) -> Result<(), Error> {
+        Command::StartLine(line).send(&mut self.interface)
+    }
+
+    /// Upload a user-defined gray scale table.
+    pub fn set_gray_scale_table(&mut self, table: &GrayScaleTable) -> Result<(), Error> {
+        Command::GrayScaleTable(*table).send(&mut self.interface)
+    }
+
+    /// Select the built-in linear gray scale table.
+    pub fn set_linear_gray_scale_table(&mut self) -> Result<(), Error> {
+        Command::LineralLUT.send(&mut self.interface)
+    }
+
+    /// Reverse columns from 79-0.
+    pub fn set_segment_remap(&mut self, remap: bool) -> Result<(), Error> {
+        Command::SegmentRemap(remap).send(&mut self.interface)
+    }
+
+    /// Scan from COM[n-1] to COM0 (where N is mux ratio).
+    pub fn set_com_reverse(&mut self, reverse: bool) -> Result<(), Error> {
+        Command::ReverseComDir(reverse).send(&mut self.interface)
+    }
+
+    /// Set vertical shift by COM.
+    pub fn set_display_offset(&mut self, offset: u8) -> Result<(), Error> {
+        Command::DisplayOffset(offset).send(&mut self.interface)
+    }
+
     /// Set the position in the framebuffer of the display limiting where any sent data should be
     /// drawn. This method can be used for changing the affected area on the screen as well
     /// as (re-)setting the start point of the next `draw` call.
-    pub fn set_draw_area(&mut self, start: (u8, u8), end: (u8, u8)) -> Result<(), DisplayError> {
-        Command::ColumnAddress(start.0 / 2, end.0 / 2).send(&mut self.interface)?;
+    pub fn set_draw_area(&mut self, start: (u8, u8), end: (u8, u8)) -> Result<(), Error> {
+        check_area::<SIZE>(start, end, "draw area")?;
+        Command::ColumnAddress((start.0 + SIZE::OFFSETX) / 2, (end.0 + SIZE::OFFSETX) / 2)
+            .send(&mut self.interface)?;
 
-        Command::RowAddress(start.1.into(), (end.1).into()).send(&mut self.interface)?;
+        Command::RowAddress(start.1, end.1).send(&mut self.interface)?;
 
         Ok(())
     }
 
+    /// Setup continuous horizontal scrolling of the area between `start` and `end` pixels.
+    /// Scrolling is deactivated before the setup, use `set_scroll_active` to start it.
+    pub fn set_horizontal_scroll(
+        &mut self,
+        direction: ScrollDirection,
+        start: (u8, u8),
+        end: (u8, u8),
+        interval: ScrollInterval,
+    ) -> Result<(), Error> {
+        check_area::<SIZE>(start, end, "scroll area")?;
+        Command::ScrollActive(false).send(&mut self.interface)?;
+        Command::HorizontalScrollSetup(
+            direction,
+            start.1,
+            interval,
+            end.1,
+            (start.0 + SIZE::OFFSETX) / 2,
+            (end.0 + SIZE::OFFSETX) / 2,
+        )
+        .send(&mut self.interface)
+    }
+
+    /// Activate or deactivate scrolling. The display RAM must not be written while
+    /// scrolling is active.
+    pub fn set_scroll_active(&mut self, active: bool) -> Result<(), Error> {
+        Command::ScrollActive(active).send(&mut self.interface)
+    }
+
+    /// Set fade out or blinking mode, the contrast is changed by the controller itself.
+    pub fn set_fade_mode(&mut self, mode: FadeMode) -> Result<(), Error> {
+        Command::FadeMode(mode).send(&mut self.interface)
+    }
+
+    /// Enable or disable zoom in mode, the upper half of the display RAM rows is shown
+    /// on the whole display with each row doubled.
+    pub fn set_zoom(&mut self, zoom: bool) -> Result<(), Error> {
+        Command::ZoomIn(zoom).send(&mut self.interface)
+    }
+
     /// Set the column address (column 2px)in the framebuffer of the display where any sent data should be
     /// drawn.
-    pub fn set_column(&mut self, column: u8) -> Result<(), DisplayError> {
-        Command::ColumnAddress(column, 160 / 2 - 1).send(&mut self.interface)
+    pub fn set_column(&mut self, column: u8) -> Result<(), Error> {
+        if column as u16 >= SIZE::WIDTH / 2 {
+            return Err(Error::InvalidParameter("column"));
+        }
+        let offset = SIZE::OFFSETX / 2;
+        let end = ((SIZE::WIDTH / 2) as u8 - 1) + offset;
+        Command::ColumnAddress(column + offset, end).send(&mut self.interface)
     }
 
     /// Set the page address in the framebuffer of the display where any sent data
     /// should be drawn.
-    pub fn set_row(&mut self, row: u8) -> Result<(), DisplayError> {
-        Command::RowAddress(row, 0x83).send(&mut self.interface)
+    pub fn set_row(&mut self, row: u8) -> Result<(), Error> {
+        if row as u16 >= SIZE::HEIGHT {
+            return Err(Error::InvalidParameter("row"));
+        }
+        Command::RowAddress(row, (SIZE::HEIGHT - 1) as u8).send(&mut self.interface)
     }
 
     /// Reset the display.
@@ -95,7 +205,7 @@ where
         &mut self,
         rst: &mut RST,
         delay: &mut DELAY,
-    ) -> Result<(), Error<(), PinE>>
+    ) -> Result<(), Error<PinE>>
     where
         RST: OutputPin<Error = PinE>,
         DELAY: DelayMs<u8>,
@@ -104,16 +214,32 @@ where
     }
 }
 
-fn inner_reset<RST, DELAY, PinE>(rst: &mut RST, delay: &mut DELAY) -> Result<(), Error<(), PinE>>
+/// Check that the area between `start` and `end` pixels lies within the display
+fn check_area<SIZE>(start: (u8, u8), end: (u8, u8), name: &'static str) -> Result<(), Error>
+where
+    SIZE: DisplaySize,
+{
+    if start.0 > end.0
+        || start.1 > end.1
+        || end.0 as u16 >= SIZE::WIDTH
+        || end.1 as u16 >= SIZE::HEIGHT
+    {
+        return Err(Error::InvalidParameter(name));
+    }
+
+    Ok(())
+}
+
+fn inner_reset<RST, DELAY, PinE>(rst: &mut RST, delay: &mut DELAY) -> Result<(), Error<PinE>>
 where
     RST: OutputPin<Error = PinE>,
     DELAY: DelayMs<u8>,
 {
-    rst.set_high().map_err(Error::Pin)?;
+    rst.set_high().map_err(Error::Reset)?;
     delay.delay_ms(1);
-    rst.set_low().map_err(Error::Pin)?;
+    rst.set_low().map_err(Error::Reset)?;
     delay.delay_ms(10);
-    rst.set_high().map_err(Error::Pin)?;
+    rst.set_high().map_err(Error::Reset)?;
     delay.delay_ms(20);
 
     Ok(())
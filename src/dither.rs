@@ -0,0 +1,341 @@
+//! Drawing of colors with more levels than Gray4.
+//!
+//! [`Dithered`] wraps any Gray4 draw target, such as the buffered graphics mode, and
+//! quantizes `Gray8`, `Gray2` or RGB colors (through their luma) to the 16 levels of the
+//! display:
+//!
+//! ```
+//! use embedded_graphics::{
+//!     image::{Image, ImageRaw},
+//!     pixelcolor::{Gray4, Gray8},
+//!     prelude::*,
+//! };
+//! use ssd1320::dither::{Dithered, Dithering};
+//!
+//! fn draw_photo<D>(display: &mut D, photo: &ImageRaw<Gray8>) -> Result<(), D::Error>
+//! where
+//!     D: DrawTarget<Color = Gray4>,
+//! {
+//!     let mut target = Dithered::new(display, Dithering::FloydSteinberg);
+//!     Image::new(photo, Point::zero()).draw(&mut target)
+//! }
+//! ```
+
+use core::{iter, marker::PhantomData, mem, ops::Range};
+
+use embedded_graphics_core::{
+    pixelcolor::{Gray4, Gray8, GrayColor},
+    prelude::*,
+    primitives::Rectangle,
+};
+
+/// 4x4 Bayer threshold matrix
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Widest target diffusing the Floyd–Steinberg error over all of its columns, the widest
+/// display, pixels beyond it are quantized without dithering
+const DIFFUSION_LEN: usize = 320;
+
+/// Quantization of the 256 luma levels to the 16 levels of the display
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dithering {
+    /// Nearest level
+    None,
+    /// 4x4 Bayer ordered dithering, the threshold depends on the display position of
+    /// a pixel, so adjacent areas match
+    Bayer4x4,
+    /// Floyd–Steinberg error diffusion, applied per image drawn with `fill_contiguous`
+    /// or area drawn with `fill_solid`, single pixels use the nearest level
+    FloydSteinberg,
+}
+
+/// Draw target quantizing colors of type `C` to the Gray4 target `T`
+#[derive(Debug)]
+pub struct Dithered<'a, T, C> {
+    target: &'a mut T,
+    dithering: Dithering,
+    color: PhantomData<C>,
+}
+
+impl<'a, T, C> Dithered<'a, T, C> {
+    /// Wrap a Gray4 draw target
+    pub fn new(target: &'a mut T, dithering: Dithering) -> Self {
+        Self {
+            target,
+            dithering,
+            color: PhantomData,
+        }
+    }
+
+    /// Get the dithering method.
+    pub fn dithering(&self) -> Dithering {
+        self.dithering
+    }
+}
+
+/// 8 bit luma of a color
+fn luma<C>(color: C) -> u8
+where
+    C: Into<Gray8>,
+{
+    color.into().luma()
+}
+
+/// Nearest Gray4 level of a luma
+fn nearest(luma: u8) -> Gray4 {
+    Gray4::new(((luma as u16 * 15 + 127) / 255) as u8)
+}
+
+/// Gray4 level of a luma at a display position with the Bayer matrix
+fn bayer(luma: u8, point: Point) -> Gray4 {
+    let threshold = BAYER_4X4[point.y.rem_euclid(4) as usize][point.x.rem_euclid(4) as usize];
+    // Offset inside of one level step, from 1/32 to 31/32 of it
+    let offset = (threshold as u16 * 2 + 1) * 255 / 32;
+    Gray4::new(((luma as u16 * 15 + offset) / 255) as u8)
+}
+
+/// Floyd–Steinberg quantization of the colors of an image `width` pixels wide, given row by
+/// row, the error is diffused over the `columns` visible in the target
+struct FloydSteinberg<I> {
+    colors: I,
+    width: usize,
+    columns: Range<usize>,
+    x: usize,
+    // Errors in 1/16 of a luma level carried to the visible pixels of the current and the
+    // next row, only the first `columns.len() + 1` cells are used
+    current: [i16; DIFFUSION_LEN + 1],
+    next: [i16; DIFFUSION_LEN + 1],
+}
+
+impl<I> FloydSteinberg<I> {
+    fn new(colors: I, width: usize, columns: Range<usize>) -> Self {
+        let end = columns.end.min(columns.start + DIFFUSION_LEN);
+        Self {
+            colors,
+            width,
+            columns: columns.start..end,
+            x: 0,
+            current: [0; DIFFUSION_LEN + 1],
+            next: [0; DIFFUSION_LEN + 1],
+        }
+    }
+}
+
+impl<I> Iterator for FloydSteinberg<I>
+where
+    I: Iterator<Item = u8>,
+{
+    type Item = Gray4;
+
+    fn next(&mut self) -> Option<Gray4> {
+        let luma = self.colors.next()?;
+        let level = if self.columns.contains(&self.x) {
+            let x = self.x - self.columns.start;
+            let value = (luma as i16 + self.current[x] / 16).clamp(0, 255);
+            let level = nearest(value as u8);
+            let error = value - level.luma() as i16 * 17;
+            // The cell after the last pixel of a row is never read
+            self.current[x + 1] += error * 7;
+            if x > 0 {
+                self.next[x - 1] += error * 3;
+            }
+            self.next[x] += error * 5;
+            self.next[x + 1] += error;
+            level
+        } else {
+            nearest(luma)
+        };
+        self.x += 1;
+        if self.x == self.width {
+            self.x = 0;
+            mem::swap(&mut self.current, &mut self.next);
+            self.next[..self.columns.len() + 1].fill(0);
+        }
+
+        Some(level)
+    }
+}
+
+impl<T, C> Dimensions for Dithered<'_, T, C>
+where
+    T: Dimensions,
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<T, C> DrawTarget for Dithered<'_, T, C>
+where
+    T: DrawTarget<Color = Gray4>,
+    C: PixelColor + Into<Gray8>,
+{
+    type Color = C;
+
+    type Error = T::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let dithering = self.dithering;
+        self.target
+            .draw_iter(pixels.into_iter().map(|Pixel(point, color)| {
+                let color = match dithering {
+                    Dithering::Bayer4x4 => bayer(luma(color), point),
+                    Dithering::None | Dithering::FloydSteinberg => nearest(luma(color)),
+                };
+                Pixel(point, color)
+            }))
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let colors = colors.into_iter().map(luma);
+        match self.dithering {
+            Dithering::None => self.target.fill_contiguous(area, colors.map(nearest)),
+            Dithering::Bayer4x4 => {
+                let levels = area
+                    .points()
+                    .zip(colors)
+                    .map(|(point, luma)| bayer(luma, point));
+                self.target.fill_contiguous(area, levels)
+            }
+            Dithering::FloydSteinberg => {
+                // Columns outside of the target are dropped, their error is not diffused
+                let visible = area.intersection(&self.target.bounding_box());
+                let start = (visible.top_left.x - area.top_left.x).max(0) as usize;
+                let columns = start..start + visible.size.width as usize;
+                let levels = FloydSteinberg::new(colors, area.size.width as usize, columns);
+                self.target.fill_contiguous(area, levels)
+            }
+        }
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        match self.dithering {
+            Dithering::None => self.target.fill_solid(area, nearest(luma(color))),
+            Dithering::Bayer4x4 | Dithering::FloydSteinberg => {
+                let len = area.size.width as usize * area.size.height as usize;
+                self.fill_contiguous(area, iter::repeat(color).take(len))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use embedded_graphics_core::pixelcolor::Rgb888;
+
+    /// Gray4 draw target of 16x16 pixels
+    struct Canvas([[u8; 16]; 16]);
+
+    impl Canvas {
+        /// Fill the whole canvas through a dithered target
+        fn fill<C>(dithering: Dithering, color: C) -> Self
+        where
+            C: PixelColor + Into<Gray8>,
+        {
+            let mut canvas = Canvas([[0; 16]; 16]);
+            let area = canvas.bounding_box();
+            Dithered::new(&mut canvas, dithering)
+                .fill_solid(&area, color)
+                .unwrap();
+            canvas
+        }
+
+        fn levels(&self) -> impl Iterator<Item = u8> + '_ {
+            self.0.iter().flatten().copied()
+        }
+
+        fn mean(&self) -> f32 {
+            self.levels().map(f32::from).sum::<f32>() / 256.0
+        }
+    }
+
+    impl OriginDimensions for Canvas {
+        fn size(&self) -> Size {
+            Size::new(16, 16)
+        }
+    }
+
+    impl DrawTarget for Canvas {
+        type Color = Gray4;
+
+        type Error = core::convert::Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            for Pixel(point, color) in pixels {
+                if self.bounding_box().contains(point) {
+                    self.0[point.y as usize][point.x as usize] = color.luma();
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn nearest_level() {
+        assert_eq!(nearest(0), Gray4::new(0));
+        assert_eq!(nearest(8), Gray4::new(0));
+        assert_eq!(nearest(9), Gray4::new(1));
+        assert_eq!(nearest(136), Gray4::new(8));
+        assert_eq!(nearest(255), Gray4::new(15));
+    }
+
+    #[test]
+    fn no_dithering_uses_one_level() {
+        let canvas = Canvas::fill(Dithering::None, Gray8::new(100));
+        assert!(canvas.levels().all(|level| level == 6));
+    }
+
+    #[test]
+    fn bayer_keeps_the_mean_level() {
+        let canvas = Canvas::fill(Dithering::Bayer4x4, Gray8::new(100));
+        assert!(canvas.levels().all(|level| level == 5 || level == 6));
+        assert!((canvas.mean() - 100.0 / 17.0).abs() < 0.1);
+
+        // Full levels are not dithered
+        let canvas = Canvas::fill(Dithering::Bayer4x4, Gray8::new(255));
+        assert!(canvas.levels().all(|level| level == 15));
+        let canvas = Canvas::fill(Dithering::Bayer4x4, Gray8::new(0));
+        assert!(canvas.levels().all(|level| level == 0));
+    }
+
+    #[test]
+    fn floyd_steinberg_keeps_the_mean_level() {
+        let canvas = Canvas::fill(Dithering::FloydSteinberg, Gray8::new(100));
+        assert!(canvas.levels().all(|level| level == 5 || level == 6));
+        assert!((canvas.mean() - 100.0 / 17.0).abs() < 0.1);
+
+        let canvas = Canvas::fill(Dithering::FloydSteinberg, Gray8::new(255));
+        assert!(canvas.levels().all(|level| level == 15));
+    }
+
+    #[test]
+    fn floyd_steinberg_diffuses_over_the_visible_columns_of_a_wide_area() {
+        let mut canvas = Canvas([[0; 16]; 16]);
+        // Only the last 16 columns of a 400 pixels wide area are visible
+        let area = Rectangle::new(Point::new(-384, 0), Size::new(400, 16));
+        Dithered::new(&mut canvas, Dithering::FloydSteinberg)
+            .fill_solid(&area, Gray8::new(100))
+            .unwrap();
+        assert!(canvas.levels().all(|level| level == 5 || level == 6));
+        assert!((canvas.mean() - 100.0 / 17.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn rgb_colors_use_their_luma() {
+        let canvas = Canvas::fill(Dithering::None, Rgb888::WHITE);
+        assert!(canvas.levels().all(|level| level == 15));
+        let canvas = Canvas::fill(Dithering::None, Rgb888::new(0, 255, 0));
+        assert!(canvas.levels().all(|level| level == 9));
+    }
+}
@@ -0,0 +1,585 @@
+//! Host-side SSD1320 emulator.
+//!
+//! [`Ssd1320Emulator`] implements `WriteOnlyDataCommand`, so any driver of this crate can be
+//! run against it on a host. It models the display RAM (GDDRAM), the column and row address
+//! window, horizontal, vertical and portrait addressing, segment remap, COM scan direction,
+//! display offset, start line, multiplex ratio and zoom in mode. Scrolling, fading and the
+//! analog settings are only recorded.
+//!
+//! [`Ssd1320z2Emulator`] wires two emulated controllers to one interface and two mock chip
+//! select pins, like the Surenoo SUR383S1000WG01 module, and reads back the image visible on
+//! the glass.
+//!
+//! Both can render the visible image into a [`Screenshot`], an in-memory Gray8 image which can
+//! be saved as a PGM file and compared against golden images.
+//!
+//! ```
+//! use ssd1320::{
+//!     config::Config, emulator::Ssd1320z2Emulator, rotation::DisplayRotation,
+//!     size::DisplaySize320x132, Ssd1320z2,
+//! };
+//!
+//! let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+//! let display = Ssd1320z2::new(
+//!     emulator.interface(),
+//!     emulator.cs1(),
+//!     emulator.cs2(),
+//!     DisplaySize320x132,
+//!     DisplayRotation::Rotate0,
+//! );
+//! let mut display = display.init(&Config::default()).unwrap();
+//! display.set_draw_area((158, 0), (161, 0)).unwrap();
+//! display.draw(&[0xa5, 0x3c]).unwrap();
+//!
+//! assert_eq!(emulator.pixel(159, 0), 0xa);
+//! assert_eq!(emulator.pixel(160, 0), 0xc);
+//! ```
+
+extern crate std;
+
+use core::cell::{Ref, RefCell};
+use core::convert::Infallible;
+use core::marker::PhantomData;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+use std::vec::Vec;
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::command::{AddrMode, Command, PortraitAddrMode};
+use crate::consts::ssd1320;
+use crate::grayscale::GrayScaleTable;
+use crate::size::{DisplaySize, DualDisplaySize};
+
+/// Number of segment outputs of the controller
+const NUM_SEGMENTS: usize = 160;
+
+/// Number of display RAM rows of the controller
+const NUM_ROWS: usize = ssd1320::NUM_PIXELS_ROWS as usize;
+
+/// Number of display RAM columns, each column holds two segments
+const NUM_COLUMNS: usize = NUM_SEGMENTS / 2;
+
+/// Emulated SSD1320 controller
+#[derive(Clone, Debug)]
+pub struct Ssd1320Emulator {
+    ram: [[u8; NUM_COLUMNS]; NUM_ROWS],
+    columns: (u8, u8),
+    rows: (u8, u8),
+    column: u8,
+    row: u8,
+    // Segment in the current column pair for the portrait addressing
+    half: u8,
+    address_mode: AddrMode,
+    portrait_address_mode: PortraitAddrMode,
+    segment_remap: bool,
+    com_reverse: bool,
+    display_offset: u8,
+    start_line: u8,
+    multiplex: u8,
+    zoom: bool,
+    display_on: bool,
+    all_on: bool,
+    invert: bool,
+    locked: bool,
+    contrast: u8,
+    gray_scale_table: GrayScaleTable,
+    commands: Vec<Command>,
+    data_len: usize,
+}
+
+impl Default for Ssd1320Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ssd1320Emulator {
+    /// Create an emulator with the reset state of the controller
+    pub fn new() -> Self {
+        Self {
+            ram: [[0; NUM_COLUMNS]; NUM_ROWS],
+            columns: (0, NUM_COLUMNS as u8 - 1),
+            rows: (0, NUM_ROWS as u8 - 1),
+            column: 0,
+            row: 0,
+            half: 0,
+            address_mode: AddrMode::Horizontal,
+            portrait_address_mode: PortraitAddrMode::Normal,
+            segment_remap: false,
+            com_reverse: false,
+            display_offset: 0,
+            start_line: 0,
+            multiplex: NUM_ROWS as u8 - 1,
+            zoom: false,
+            display_on: false,
+            all_on: false,
+            invert: false,
+            locked: false,
+            contrast: 0x7f,
+            gray_scale_table: GrayScaleTable::LINEAR,
+            commands: Vec::new(),
+            data_len: 0,
+        }
+    }
+
+    /// Commands received since the emulator was created, including the ones ignored while
+    /// the command lock was active
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    /// Number of data bytes received since the emulator was created
+    pub fn data_len(&self) -> usize {
+        self.data_len
+    }
+
+    /// Gray level stored in the display RAM for a segment (0-159) of a row (0-159), the
+    /// segment is counted before remapping
+    pub fn ram_pixel(&self, segment: usize, row: usize) -> u8 {
+        let shift = segment % 2 * 4;
+        (self.ram[row][segment / 2] >> shift) & 0xf
+    }
+
+    /// Gray level driven on a segment output (0-159) while a COM output (0-159) is scanned
+    ///
+    /// Returns 0 for COM outputs beyond the multiplex ratio and while the display is off.
+    pub fn output(&self, segment: usize, com: usize) -> u8 {
+        if !self.display_on {
+            return 0;
+        }
+        // Position of the COM output in the scan, the display offset shifts the outputs
+        let mut line = (com + self.display_offset as usize) % NUM_ROWS;
+        if self.com_reverse {
+            line = NUM_ROWS - 1 - line;
+        }
+        if line > self.multiplex as usize {
+            return 0;
+        }
+        if self.zoom {
+            line /= 2;
+        }
+        let row = (line + self.start_line as usize) % NUM_ROWS;
+        let segment = if self.segment_remap {
+            NUM_SEGMENTS - 1 - segment
+        } else {
+            segment
+        };
+        let level = if self.all_on {
+            0xf
+        } else {
+            self.ram_pixel(segment, row)
+        };
+
+        if self.invert {
+            0xf - level
+        } else {
+            level
+        }
+    }
+
+    /// Render the image visible on a glass of the given size connected to the first segment
+    /// (shifted by `SIZE::OFFSETX`) and the first COM outputs of the controller
+    pub fn render<SIZE>(&self, _size: SIZE) -> Screenshot
+    where
+        SIZE: DisplaySize,
+    {
+        Screenshot::from_fn(SIZE::WIDTH, SIZE::HEIGHT, |x, y| {
+            self.output(x as usize + SIZE::OFFSETX as usize, y as usize)
+        })
+    }
+
+    /// Whether zoom in mode is enabled
+    pub fn zoom(&self) -> bool {
+        self.zoom
+    }
+
+    /// Current contrast
+    pub fn contrast(&self) -> u8 {
+        self.contrast
+    }
+
+    /// Gray scale table in use, the linear one unless a table was uploaded
+    pub fn gray_scale_table(&self) -> GrayScaleTable {
+        self.gray_scale_table
+    }
+
+    fn command(&mut self, command: Command) {
+        self.commands.push(command);
+        if self.locked {
+            if let Command::DisplayLock(false) = command {
+                self.locked = false;
+            }
+            return;
+        }
+        match command {
+            Command::AddressMode(mode) => self.address_mode = mode,
+            Command::ColumnAddress(start, end) => {
+                self.columns = (start, end);
+                self.column = start;
+                self.half = 0;
+            }
+            Command::RowAddress(start, end) => {
+                self.rows = (start, end);
+                self.row = start;
+            }
+            Command::PortraitAddressMode(mode) => self.portrait_address_mode = mode,
+            Command::Contrast(contrast) => self.contrast = contrast,
+            Command::SegmentRemap(remap) => self.segment_remap = remap,
+            Command::StartLine(line) => self.start_line = line,
+            Command::AllOn(on) => self.all_on = on,
+            Command::Invert(invert) => self.invert = invert,
+            Command::Multiplex(ratio) => self.multiplex = ratio.wrapping_sub(1),
+            Command::DisplayOn(on) => self.display_on = on,
+            Command::LineralLUT => self.gray_scale_table = GrayScaleTable::LINEAR,
+            Command::GrayScaleTable(table) => self.gray_scale_table = table,
+            Command::ReverseComDir(reverse) => self.com_reverse = reverse,
+            Command::DisplayOffset(offset) => self.display_offset = offset,
+            Command::ZoomIn(zoom) => self.zoom = zoom,
+            Command::DisplayLock(lock) => self.locked = lock,
+            _ => {}
+        }
+    }
+
+    fn data(&mut self, byte: u8) {
+        self.data_len += 1;
+        let (row, column) = (self.row as usize, self.column as usize);
+        if self.portrait_address_mode == PortraitAddrMode::Portrait {
+            // The two pixels of the byte are placed in two consecutive rows of one segment
+            let segment = column * 2 + self.half as usize;
+            self.set_ram_pixel(segment, row, byte & 0xf);
+            self.set_ram_pixel(segment, (row + 1) % NUM_ROWS, byte >> 4);
+        } else if let Some(cell) = self.ram.get_mut(row).and_then(|r| r.get_mut(column)) {
+            *cell = byte;
+        }
+        self.advance();
+    }
+
+    fn set_ram_pixel(&mut self, segment: usize, row: usize, level: u8) {
+        if let Some(cell) = self.ram.get_mut(row).and_then(|r| r.get_mut(segment / 2)) {
+            let shift = segment % 2 * 4;
+            *cell = (*cell & !(0xf << shift)) | (level << shift);
+        }
+    }
+
+    /// Move the address pointer to the next position inside of the window
+    fn advance(&mut self) {
+        let portrait = self.portrait_address_mode == PortraitAddrMode::Portrait;
+        // In the portrait addressing a column is passed in two segment steps and a row
+        // step covers two rows
+        let row_step = if portrait { 2 } else { 1 };
+        let next_column = |emulator: &mut Self| {
+            if portrait && emulator.half == 0 {
+                emulator.half = 1;
+                return false;
+            }
+            emulator.half = 0;
+            if emulator.column >= emulator.columns.1 {
+                emulator.column = emulator.columns.0;
+                true
+            } else {
+                emulator.column += 1;
+                false
+            }
+        };
+        let next_row = |emulator: &mut Self| {
+            if emulator.row as u16 + row_step > emulator.rows.1 as u16 {
+                emulator.row = emulator.rows.0;
+                true
+            } else {
+                emulator.row += row_step as u8;
+                false
+            }
+        };
+        match self.address_mode {
+            AddrMode::Horizontal => {
+                if next_column(self) {
+                    next_row(self);
+                }
+            }
+            AddrMode::Vertical => {
+                if next_row(self) {
+                    next_column(self);
+                }
+            }
+        }
+    }
+}
+
+impl WriteOnlyDataCommand for Ssd1320Emulator {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let bytes = match cmd {
+            DataFormat::U8(bytes) => bytes,
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        };
+        for command in Command::decode_all(bytes) {
+            let command = command.map_err(|_| DisplayError::InvalidFormatError)?;
+            self.command(command);
+        }
+
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        match buf {
+            DataFormat::U8(bytes) => bytes.iter().for_each(|&byte| self.data(byte)),
+            DataFormat::U8Iter(bytes) => bytes.for_each(|byte| self.data(byte)),
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+
+        Ok(())
+    }
+}
+
+/// Gray8 image of an emulated glass
+///
+/// The 16 gray levels of the controller are scaled to the full 8 bit range, so level 15
+/// becomes 255. The image can be written to and read from binary PGM (P5) files.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Screenshot {
+    width: u16,
+    height: u16,
+    pixels: Vec<u8>,
+}
+
+impl Screenshot {
+    /// Scale of a 4 bit gray level to 8 bits
+    const SCALE: u8 = 0x11;
+
+    /// Create an image from the gray levels (0-15) returned for every pixel
+    fn from_fn<F>(width: u16, height: u16, mut level: F) -> Self
+    where
+        F: FnMut(u16, u16) -> u8,
+    {
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| level(x, y) * Self::SCALE)
+            .collect();
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Width in pixels
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Height in pixels
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Gray8 pixels row by row
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Gray8 value of a pixel
+    pub fn pixel(&self, x: u16, y: u16) -> u8 {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Encode the image as a binary PGM file
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let mut pgm = std::format!("P5\n{} {}\n255\n", self.width, self.height).into_bytes();
+        pgm.extend_from_slice(&self.pixels);
+        pgm
+    }
+
+    /// Decode a binary PGM file with 8 bit pixels, comments are not supported
+    pub fn from_pgm(pgm: &[u8]) -> Option<Self> {
+        // The header is made of four whitespace separated fields followed by a single
+        // whitespace character
+        let mut fields = [0usize; 3];
+        let mut rest = pgm.strip_prefix(b"P5")?;
+        for field in fields.iter_mut() {
+            let start = rest.iter().position(|b| !b.is_ascii_whitespace())?;
+            rest = &rest[start..];
+            let len = rest.iter().position(|b| !b.is_ascii_digit())?;
+            *field = core::str::from_utf8(&rest[..len]).ok()?.parse().ok()?;
+            rest = &rest[len..];
+        }
+        let [width, height, max] = fields;
+        let pixels = rest.get(1..)?;
+        if max != 255 || pixels.len() != width * height {
+            return None;
+        }
+
+        Some(Self {
+            width: width as u16,
+            height: height as u16,
+            pixels: pixels.to_vec(),
+        })
+    }
+
+    /// Write the image to a binary PGM file
+    pub fn save_pgm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_pgm())
+    }
+
+    /// Read an image from a binary PGM file
+    pub fn load_pgm<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let pgm = fs::read(path)?;
+        Self::from_pgm(&pgm).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "PGM"))
+    }
+}
+
+/// Two controllers sharing one interface, each one listens while its chip select is low
+#[derive(Debug, Default)]
+struct Bus {
+    controllers: [Ssd1320Emulator; 2],
+    selected: [bool; 2],
+}
+
+impl Bus {
+    fn selected(&mut self) -> impl Iterator<Item = &mut Ssd1320Emulator> {
+        let selected = self.selected;
+        self.controllers
+            .iter_mut()
+            .zip(selected)
+            .filter_map(|(controller, selected)| if selected { Some(controller) } else { None })
+    }
+}
+
+/// Emulated module made of two SSD1320 controllers placed side by side
+///
+/// The first controller drives the left half of the glass, the second one is mounted upside
+/// down and drives the right half with its segments and COM outputs in the reverse order.
+/// The glass rows are connected to the middle COM outputs shifted by `SIZE::OFFSETY`.
+#[derive(Debug)]
+pub struct Ssd1320z2Emulator<SIZE> {
+    bus: Rc<RefCell<Bus>>,
+    size: PhantomData<SIZE>,
+}
+
+impl<SIZE> Ssd1320z2Emulator<SIZE>
+where
+    SIZE: DualDisplaySize,
+{
+    /// Create a module with both controllers in their reset state
+    pub fn new(_size: SIZE) -> Self {
+        Self {
+            bus: Rc::new(RefCell::new(Bus::default())),
+            size: PhantomData,
+        }
+    }
+
+    /// Interface connected to both controllers
+    pub fn interface(&self) -> EmulatedInterface {
+        EmulatedInterface {
+            bus: self.bus.clone(),
+        }
+    }
+
+    /// Chip select pin of the first controller
+    pub fn cs1(&self) -> EmulatedChipSelect {
+        EmulatedChipSelect {
+            bus: self.bus.clone(),
+            index: 0,
+        }
+    }
+
+    /// Chip select pin of the second controller
+    pub fn cs2(&self) -> EmulatedChipSelect {
+        EmulatedChipSelect {
+            bus: self.bus.clone(),
+            index: 1,
+        }
+    }
+
+    /// State of the first (0) or the second (1) controller
+    pub fn controller(&self, index: usize) -> Ref<'_, Ssd1320Emulator> {
+        Ref::map(self.bus.borrow(), |bus| &bus.controllers[index])
+    }
+
+    /// Render the image visible on the glass with both halves merged
+    pub fn render(&self) -> Screenshot {
+        Screenshot::from_fn(SIZE::WIDTH, SIZE::HEIGHT, |x, y| self.pixel(x, y))
+    }
+
+    /// Gray level of a glass pixel
+    pub fn pixel(&self, x: u16, y: u16) -> u8 {
+        let half_width = SIZE::Half::WIDTH;
+        let offset = SIZE::OFFSETY as usize;
+        let (x, y) = (x as usize, y as usize);
+        let bus = self.bus.borrow();
+        if x < half_width as usize {
+            let com = NUM_ROWS - 1 - offset - y;
+            bus.controllers[0].output(x + SIZE::Half::OFFSETX as usize, com)
+        } else {
+            let x = x - half_width as usize;
+            let segment = NUM_SEGMENTS - 1 - x - SIZE::Half::OFFSETX as usize;
+            bus.controllers[1].output(segment, offset + y)
+        }
+    }
+}
+
+/// Interface of an emulated module, commands and data reach the selected controllers
+#[derive(Debug)]
+pub struct EmulatedInterface {
+    bus: Rc<RefCell<Bus>>,
+}
+
+impl WriteOnlyDataCommand for EmulatedInterface {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let bytes = match cmd {
+            DataFormat::U8(bytes) => bytes,
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        };
+        for controller in self.bus.borrow_mut().selected() {
+            controller.send_commands(DataFormat::U8(bytes))?;
+        }
+
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let mut bus = self.bus.borrow_mut();
+        match buf {
+            DataFormat::U8(bytes) => {
+                for controller in bus.selected() {
+                    controller.send_data(DataFormat::U8(bytes))?;
+                }
+            }
+            DataFormat::U8Iter(bytes) => {
+                for byte in bytes {
+                    for controller in bus.selected() {
+                        controller.data(byte);
+                    }
+                }
+            }
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+
+        Ok(())
+    }
+}
+
+/// Mock chip select pin of an emulated module, active low
+#[derive(Debug)]
+pub struct EmulatedChipSelect {
+    bus: Rc<RefCell<Bus>>,
+    index: usize,
+}
+
+impl OutputPin for EmulatedChipSelect {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.bus.borrow_mut().selected[self.index] = true;
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.bus.borrow_mut().selected[self.index] = false;
+        Ok(())
+    }
+}
@@ -1,8 +1,57 @@
+//! Errors in this crate.
+
+use core::convert::Infallible;
+use core::fmt;
+
+use display_interface::DisplayError;
+
 /// Errors in this crate
-#[derive(Debug)]
-pub enum Error<CommE, PinE> {
+///
+/// `PinE` is the error type of the chip select and reset pins, drivers without any pins
+/// use [`Infallible`].
+#[derive(Clone, Debug)]
+pub enum Error<PinE = Infallible> {
     /// Communication error
-    Comm(CommE),
-    /// Pin setting error
-    Pin(PinE),
+    Comm(DisplayError),
+    /// Chip select pin setting error
+    ChipSelect(PinE),
+    /// Reset pin setting error
+    Reset(PinE),
+    /// Parameter out of the range accepted by the controller, contains the parameter name
+    InvalidParameter(&'static str),
+    /// Command stream which cannot be decoded, contains the offset of the failing command
+    Decode(usize),
+}
+
+impl Error<Infallible> {
+    /// Convert an error of a driver without pins into an error with any pin error type.
+    pub fn with_pin_error<PinE>(self) -> Error<PinE> {
+        match self {
+            Error::Comm(error) => Error::Comm(error),
+            Error::InvalidParameter(name) => Error::InvalidParameter(name),
+            Error::Decode(offset) => Error::Decode(offset),
+            Error::ChipSelect(never) | Error::Reset(never) => match never {},
+        }
+    }
+}
+
+impl<PinE> From<DisplayError> for Error<PinE> {
+    fn from(error: DisplayError) -> Self {
+        Error::Comm(error)
+    }
+}
+
+impl<PinE> fmt::Display for Error<PinE>
+where
+    PinE: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Comm(error) => write!(f, "display interface error: {:?}", error),
+            Error::ChipSelect(error) => write!(f, "chip select pin error: {:?}", error),
+            Error::Reset(error) => write!(f, "reset pin error: {:?}", error),
+            Error::InvalidParameter(name) => write!(f, "{} out of range", name),
+            Error::Decode(offset) => write!(f, "cannot decode command at byte {}", offset),
+        }
+    }
 }
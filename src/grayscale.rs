@@ -0,0 +1,70 @@
+//! Gray scale lookup table.
+
+/// The highest pulse width accepted by the controller, in units of DCLK.
+pub const MAX_PULSE_WIDTH: u8 = 0x3f;
+
+/// Pulse width of the brightest level used by the built-in linear table and the presets.
+const FULL_SCALE: u8 = 60;
+
+/// User-defined gray scale table.
+///
+/// Holds the pulse widths of gray scale levels GS1 to GS15 in units of DCLK, GS0 always has
+/// zero pulse width. Widths must not decrease and must not be greater than
+/// [`MAX_PULSE_WIDTH`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GrayScaleTable([u8; 15]);
+
+impl GrayScaleTable {
+    /// Same values as the built-in linear table
+    pub const LINEAR: Self = Self([4, 8, 12, 16, 20, 24, 28, 32, 36, 40, 44, 48, 52, 56, 60]);
+
+    /// Gamma 1.8 curve
+    pub const GAMMA_1_8: Self = Self([1, 2, 3, 6, 8, 12, 15, 19, 24, 29, 34, 40, 46, 53, 60]);
+
+    /// Gamma 2.2 curve
+    pub const GAMMA_2_2: Self = Self([1, 2, 3, 4, 5, 8, 11, 15, 20, 25, 30, 37, 44, 52, 60]);
+
+    /// Gamma 2.5 curve
+    pub const GAMMA_2_5: Self = Self([1, 2, 3, 4, 5, 6, 9, 12, 17, 22, 28, 34, 42, 50, 60]);
+
+    /// Create a table from the pulse widths of GS1 to GS15, returns `None` if the widths
+    /// decrease or are out of range.
+    pub fn new(widths: [u8; 15]) -> Option<Self> {
+        let mut previous = 0;
+        for &width in widths.iter() {
+            if width < previous || width > MAX_PULSE_WIDTH {
+                return None;
+            }
+            previous = width;
+        }
+
+        Some(Self(widths))
+    }
+
+    /// Build a table following the `gamma` curve with the brightest level equal to the
+    /// linear table one. Each level is at least one DCLK longer than the previous one, so
+    /// all 16 levels stay distinguishable.
+    pub fn from_gamma(gamma: f32) -> Self {
+        let mut widths = [0; 15];
+        let mut previous = 0;
+        for (level, width) in widths.iter_mut().enumerate() {
+            let ratio = (level + 1) as f32 / 15.0;
+            let ideal = libm::roundf(FULL_SCALE as f32 * libm::powf(ratio, gamma)) as u8;
+            *width = ideal.max(previous + 1).min(FULL_SCALE);
+            previous = *width;
+        }
+
+        Self(widths)
+    }
+
+    /// Pulse widths of GS1 to GS15
+    pub fn widths(&self) -> &[u8; 15] {
+        &self.0
+    }
+}
+
+impl Default for GrayScaleTable {
+    fn default() -> Self {
+        Self::LINEAR
+    }
+}
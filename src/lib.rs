@@ -2,201 +2,374 @@
 //! Generic SPI interface for display drivers
 
 pub mod buffered_graphics;
-mod command;
+pub mod command;
+pub mod config;
 mod consts;
+mod controllers;
 mod display;
-mod error;
+pub mod dither;
+#[cfg(feature = "std")]
+pub mod emulator;
+pub mod error;
+pub mod grayscale;
+pub mod mode;
+pub mod mono_graphics;
+mod raw_graphics;
+pub mod rotation;
+pub mod size;
 
-use core::cmp::min;
-
-use display_interface::{DisplayError, WriteOnlyDataCommand};
+use display_interface::WriteOnlyDataCommand;
 use embedded_hal::{blocking::delay::DelayMs, digital::v2::OutputPin};
 
-use consts::{ssd1320, ssd1320z2};
-use display::Ssd1320;
+use command::Command;
+use config::Config;
+use controllers::{Controllers, Frame};
 use error::Error;
+use grayscale::GrayScaleTable;
+use mode::{InitializedMode, RawMode, UninitializedMode};
+use rotation::DisplayRotation;
+use size::{DisplaySize, DisplaySize320x132, DualDisplaySize};
+
+pub use command::{FadeInterval, FadeMode, ScrollDirection, ScrollInterval};
+pub use display::Ssd1320;
+
+/// Controller of a [`Ssd1320z2`] targeted by `send_command`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Controller {
+    /// Both controllers at once
+    Both,
+    /// Controller selected by the first chip select pin
+    First,
+    /// Controller selected by the second chip select pin
+    Second,
+}
 
+/// Variant for Surenoo SUR383S1000WG01
+/// Two SSD1320 controllers on board with single interface
+///
+/// The driver starts in [`UninitializedMode`](mode::UninitializedMode) after `new`, `init`
+/// turns it into [`RawMode`] which can be switched to the buffered graphics mode and back.
 #[derive(Copy, Clone, Debug)]
-struct Frame {
-    start: (u16, u16),
-    end: (u16, u16),
+pub struct Ssd1320z2<DI, CS1, CS2, SIZE = DisplaySize320x132, MODE = RawMode>
+where
+    SIZE: DualDisplaySize,
+{
+    controllers: Controllers<DI, CS1, CS2, SIZE>,
+    scroll_area: Option<Frame>,
+    zoom: bool,
+    mode: MODE,
 }
 
-impl Frame {
-    fn new() -> Self {
-        Self {
-            start: (0, 0),
-            end: (ssd1320z2::PIXEL_COL_MAX, ssd1320z2::PIXEL_ROW_MAX),
-        }
+impl<DI, CS1, CS2, SIZE, MODE> Ssd1320z2<DI, CS1, CS2, SIZE, MODE>
+where
+    SIZE: DualDisplaySize,
+{
+    /// Get the display rotation.
+    pub fn rotation(&self) -> DisplayRotation {
+        self.controllers.rotation
     }
 
-    fn normalize(&self) -> Self {
-        Self {
-            start: (self.start.0 % ssd1320::NUM_PIXELS_COLS, self.start.1),
-            end: (self.end.0 % ssd1320::NUM_PIXELS_COLS, self.end.1),
-        }
+    /// Get the zoom in mode.
+    pub fn zoom(&self) -> bool {
+        self.zoom
     }
 
-    fn split_to_two(&self) -> (Self, Self) {
-        let start_x = self.start.0;
-        let end_x = self.end.0;
-
-        (
-            Self {
-                start: (start_x, self.start.1),
-                end: (ssd1320::PIXEL_COL_MAX, self.end.1),
-            },
-            Self {
-                start: (0, self.start.1),
-                end: (end_x, self.end.1),
-            },
-        )
+    /// Release the interface and the chip select pins.
+    pub fn release(self) -> (DI, CS1, CS2) {
+        self.controllers.release()
     }
 
-    fn as_u8(&self) -> ((u8, u8), (u8, u8)) {
-        (
-            (self.start.0 as u8, self.start.1 as u8),
-            (self.end.0 as u8, self.end.1 as u8),
-        )
+    /// Number of framebuffer rows shown on the display
+    fn canvas_height(&self) -> u16 {
+        if self.zoom {
+            SIZE::HEIGHT / 2
+        } else {
+            SIZE::HEIGHT
+        }
     }
-}
 
-/// Variant for Surenoo SUR383S1000WG01
-/// Two SSD1320 controllers on board with single interface
-#[derive(Copy, Clone, Debug)]
-pub struct Ssd1320z2<DI, CS1, CS2> {
-    interface: Ssd1320<DI>,
-    frame: Frame,
-    position: u16,
-    cs1: CS1,
-    cs2: CS2,
+    /// Convert the display into another mode keeping the interface, pins and settings.
+    fn into_mode<MODE2>(self, mode: MODE2) -> Ssd1320z2<DI, CS1, CS2, SIZE, MODE2> {
+        Ssd1320z2 {
+            controllers: self.controllers,
+            scroll_area: self.scroll_area,
+            zoom: self.zoom,
+            mode,
+        }
+    }
 }
 
-impl<DI, CS1, CS2> Ssd1320z2<DI, CS1, CS2>
+impl<DI, CS1, CS2, SIZE> Ssd1320z2<DI, CS1, CS2, SIZE, UninitializedMode>
 where
     DI: WriteOnlyDataCommand,
     CS1: OutputPin,
-    CS2: OutputPin,
+    CS2: OutputPin<Error = CS1::Error>,
+    SIZE: DualDisplaySize,
 {
     /// Create a SSD1320z2 interface
-    pub fn new(interface: DI, cs1: CS1, cs2: CS2) -> Self {
+    pub fn new(interface: DI, cs1: CS1, cs2: CS2, _size: SIZE, rotation: DisplayRotation) -> Self {
         Self {
-            interface: Ssd1320::new(interface),
-            frame: Frame::new(),
-            position: 0,
-            cs1,
-            cs2,
+            controllers: Controllers::new(interface, cs1, cs2, rotation),
+            scroll_area: None,
+            zoom: false,
+            mode: UninitializedMode,
         }
     }
 
-    fn select_one(&mut self) {
-        self.cs2.set_high().ok();
-        self.cs1.set_low().ok();
+    /// Reset the display.
+    pub fn reset<RST, DELAY>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+    ) -> Result<(), Error<CS1::Error>>
+    where
+        RST: OutputPin<Error = CS1::Error>,
+        DELAY: DelayMs<u8>,
+    {
+        self.controllers.interface.reset(rst, delay)
     }
 
-    fn select_two(&mut self) {
-        self.cs1.set_high().ok();
-        self.cs2.set_low().ok();
-    }
+    /// Initialise both controllers with the register values of the configuration.
+    ///
+    /// The orientation settings (display offset, segment remap and COM scan direction)
+    /// are managed by the driver for each controller.
+    pub fn init(
+        mut self,
+        config: &Config,
+    ) -> Result<Ssd1320z2<DI, CS1, CS2, SIZE>, Error<CS1::Error>> {
+        let (remap, com_reverse) = self.controllers.orientation();
+        self.controllers.select_one()?;
+        self.controllers
+            .interface
+            .init(&config.with_orientation(
+                Controllers::<DI, CS1, CS2, SIZE>::display_offset(com_reverse),
+                remap,
+                com_reverse,
+            ))
+            .map_err(Error::with_pin_error)?;
+        self.controllers.select_two()?;
+        self.controllers
+            .interface
+            .init(&config.with_orientation(
+                Controllers::<DI, CS1, CS2, SIZE>::display_offset(!com_reverse),
+                !remap,
+                !com_reverse,
+            ))
+            .map_err(Error::with_pin_error)?;
+        self.controllers.unselect_all()?;
 
-    fn select_all(&mut self) {
-        self.cs1.set_low().ok();
-        self.cs2.set_low().ok();
+        Ok(self.into_mode(RawMode))
     }
+}
 
-    fn unselect_all(&mut self) {
-        self.cs1.set_high().ok();
-        self.cs2.set_high().ok();
+impl<DI, CS1, CS2, SIZE, MODE> Ssd1320z2<DI, CS1, CS2, SIZE, MODE>
+where
+    DI: WriteOnlyDataCommand,
+    CS1: OutputPin,
+    CS2: OutputPin<Error = CS1::Error>,
+    SIZE: DualDisplaySize,
+    MODE: InitializedMode,
+{
+    /// Send a single command to one or both controllers.
+    ///
+    /// The command is sent as is, so it can change registers the driver relies on, like the
+    /// draw area or the orientation. The first controller drives the left half of the display
+    /// unless it is rotated by 180 or 270 degrees.
+    pub fn send_command(
+        &mut self,
+        command: Command,
+        controller: Controller,
+    ) -> Result<(), Error<CS1::Error>> {
+        match controller {
+            Controller::Both => self.controllers.select_all()?,
+            Controller::First => self.controllers.select_one()?,
+            Controller::Second => self.controllers.select_two()?,
+        }
+        self.controllers
+            .interface
+            .send_command(command)
+            .map_err(Error::with_pin_error)?;
+        self.controllers.unselect_all()?;
+
+        Ok(())
     }
 
-    pub fn init(&mut self) -> Result<(), DisplayError> {
-        self.select_one();
-        self.interface.init(0x0e, false, true)?;
-        self.select_two();
-        self.interface.init(0x92, true, false)?;
-        self.unselect_all();
+    /// Set the display rotation.
+    ///
+    /// The display RAM content is not transformed, so the image must be redrawn. The buffered
+    /// graphics modes send the whole framebuffer with the next flush, bypassing the diff
+    /// flush comparison once.
+    pub fn set_rotation(&mut self, rotation: DisplayRotation) -> Result<(), Error<CS1::Error>> {
+        self.controllers.rotation = rotation;
+        self.mode.invalidate();
+        let (remap, com_reverse) = self.controllers.orientation();
+        self.controllers.select_one()?;
+        self.controllers.set_orientation(remap, com_reverse)?;
+        self.controllers.select_two()?;
+        self.controllers.set_orientation(!remap, !com_reverse)?;
+        self.controllers.unselect_all()?;
 
         Ok(())
     }
 
-    pub fn set_draw_area(
+    /// Setup continuous horizontal scrolling of the area between `start` and `end` pixels
+    /// in the landscape orientation.
+    ///
+    /// Each controller scrolls only its own display RAM, so the area must lie inside of the
+    /// left or the right half of the display, an area crossing the middle would wrap around
+    /// in each half instead of scrolling as one image. Scrolling is deactivated before the
+    /// setup, use `set_scroll_active` to start it.
+    pub fn set_horizontal_scroll(
         &mut self,
+        direction: ScrollDirection,
         start: (u16, u16),
         end: (u16, u16),
-    ) -> Result<(), DisplayError> {
-        self.frame = Frame { start, end };
-        if start.0 < ssd1320::NUM_PIXELS_COLS && end.0 >= ssd1320::NUM_PIXELS_COLS {
-            let (one, two) = self.frame.split_to_two();
-            let one = one.normalize().as_u8();
-            let two = two.normalize().as_u8();
-            self.position = 0;
-            self.select_one();
-            self.interface.set_draw_area(one.0, one.1)?;
-            self.select_two();
-            self.interface.set_draw_area(two.0, two.1)?;
-        } else {
-            if start.0 < ssd1320::NUM_PIXELS_COLS {
-                self.select_one();
-            } else {
-                self.select_two();
-            }
-            let adopted_frame = self.frame.normalize().as_u8();
-            self.interface
-                .set_draw_area(adopted_frame.0, adopted_frame.1)?;
+        interval: ScrollInterval,
+    ) -> Result<(), Error<CS1::Error>> {
+        let half_width = SIZE::Half::WIDTH;
+        let area = Frame { start, end };
+        if !area.is_within(SIZE::WIDTH, SIZE::HEIGHT)
+            || (start.0 < half_width && end.0 >= half_width)
+        {
+            return Err(Error::InvalidParameter("scroll area"));
         }
-        self.unselect_all();
+        // Stop scrolling in both halves, a previous area may be in the other one
+        self.scroll_area = None;
+        self.set_scroll_active(false)?;
+        // Scrolling works on the segment outputs, the controller driving the right half
+        // always has remapped segments, so it has to move its columns the other way round.
+        let direction = if start.0 < half_width {
+            self.controllers.select_left()?;
+            direction
+        } else {
+            self.controllers.select_right()?;
+            direction.reverse()
+        };
+        let adopted_area = area.normalize(half_width).as_u8();
+        self.controllers
+            .interface
+            .set_horizontal_scroll(direction, adopted_area.0, adopted_area.1, interval)
+            .map_err(Error::with_pin_error)?;
+        self.controllers.unselect_all()?;
+        self.scroll_area = Some(area);
 
         Ok(())
     }
 
-    pub fn draw(&mut self, buffer: &[u8]) -> Result<(), DisplayError> {
-        let Frame { start, end } = self.frame;
-        if start.0 < ssd1320::NUM_PIXELS_COLS && end.0 >= ssd1320::NUM_PIXELS_COLS {
-            let x_size = end.0 - start.0 + 1;
-            let x_limit = ssd1320::NUM_PIXELS_COLS - start.0;
-            let buffer_len = buffer.len() as u16;
-            let mut index = 0;
-
-            while index < buffer_len {
-                let advance = if self.position < x_limit {
-                    self.select_one();
-
-                    (x_limit - self.position) / 2
-                } else {
-                    self.select_two();
-
-                    (x_size - self.position) / 2
-                };
-                let available_advance = min(advance, buffer_len - index);
-                let end_index = available_advance + index;
-                self.interface
-                    .draw(&buffer[index as usize..end_index as usize])?;
-                self.position = (self.position + available_advance * 2) % x_size;
-                index = end_index;
-            }
-        } else {
-            if start.0 < ssd1320::NUM_PIXELS_COLS {
-                self.select_one();
-            } else {
-                self.select_two();
+    /// Activate or deactivate scrolling on the controller covering the scroll area, or on
+    /// both controllers at once without a scroll area. The display RAM must not be written
+    /// while scrolling is active.
+    pub fn set_scroll_active(&mut self, active: bool) -> Result<(), Error<CS1::Error>> {
+        match self.scroll_area {
+            Some(Frame { end, .. }) if end.0 < SIZE::Half::WIDTH => {
+                self.controllers.select_left()?
             }
-            self.interface.draw(buffer)?;
+            Some(_) => self.controllers.select_right()?,
+            None => self.controllers.select_all()?,
         }
-        self.unselect_all();
+        self.controllers
+            .interface
+            .set_scroll_active(active)
+            .map_err(Error::with_pin_error)?;
+        self.controllers.unselect_all()?;
 
         Ok(())
     }
 
-    /// Reset the display.
-    pub fn reset<RST, DELAY, PinE>(
+    /// Set fade out or blinking mode on both controllers at once, so the halves start
+    /// fading in the same frame.
+    pub fn set_fade_mode(&mut self, mode: FadeMode) -> Result<(), Error<CS1::Error>> {
+        self.controllers.select_all()?;
+        self.controllers
+            .interface
+            .set_fade_mode(mode)
+            .map_err(Error::with_pin_error)?;
+        self.controllers.unselect_all()?;
+
+        Ok(())
+    }
+
+    /// Enable or disable zoom in mode on both controllers at once, the upper half of the
+    /// display rows is shown on the whole display with each row doubled.
+    pub fn set_zoom(&mut self, zoom: bool) -> Result<(), Error<CS1::Error>> {
+        self.controllers.select_all()?;
+        self.controllers
+            .interface
+            .set_zoom(zoom)
+            .map_err(Error::with_pin_error)?;
+        self.controllers.unselect_all()?;
+        self.zoom = zoom;
+
+        Ok(())
+    }
+
+    /// Set display start line from 0-159 on both controllers at once.
+    ///
+    /// Both controllers show the same display RAM rows on the same glass rows, their
+    /// display offsets are applied to the COM lines after the start line, so the same value
+    /// keeps both halves aligned. The image wraps around the 160 rows of the display RAM,
+    /// rows below the display height become visible while scrolling.
+    pub fn set_start_line(&mut self, line: u8) -> Result<(), Error<CS1::Error>> {
+        self.controllers.select_all()?;
+        self.controllers
+            .interface
+            .set_start_line(line)
+            .map_err(Error::with_pin_error)?;
+        self.controllers.unselect_all()?;
+
+        Ok(())
+    }
+
+    /// Upload a user-defined gray scale table to both controllers.
+    pub fn set_gray_scale_table(
         &mut self,
-        rst: &mut RST,
-        delay: &mut DELAY,
-    ) -> Result<(), Error<(), PinE>>
-    where
-        RST: OutputPin<Error = PinE>,
-        DELAY: DelayMs<u8>,
-    {
-        self.interface.reset(rst, delay)
+        table: &GrayScaleTable,
+    ) -> Result<(), Error<CS1::Error>> {
+        self.controllers.select_all()?;
+        self.controllers
+            .interface
+            .set_gray_scale_table(table)
+            .map_err(Error::with_pin_error)?;
+        self.controllers.unselect_all()?;
+
+        Ok(())
+    }
+
+    /// Select the built-in linear gray scale table on both controllers.
+    pub fn set_linear_gray_scale_table(&mut self) -> Result<(), Error<CS1::Error>> {
+        self.controllers.select_all()?;
+        self.controllers
+            .interface
+            .set_linear_gray_scale_table()
+            .map_err(Error::with_pin_error)?;
+        self.controllers.unselect_all()?;
+
+        Ok(())
+    }
+}
+
+impl<DI, CS1, CS2, SIZE> Ssd1320z2<DI, CS1, CS2, SIZE, RawMode>
+where
+    DI: WriteOnlyDataCommand,
+    CS1: OutputPin,
+    CS2: OutputPin<Error = CS1::Error>,
+    SIZE: DualDisplaySize,
+{
+    /// Set the area of the display in the landscape orientation where any sent data
+    /// should be drawn, the next `draw` call starts at its top left corner.
+    ///
+    /// Every data byte fills a display RAM column of two pixels, odd edges are widened to
+    /// whole columns.
+    pub fn set_draw_area(
+        &mut self,
+        start: (u16, u16),
+        end: (u16, u16),
+    ) -> Result<(), Error<CS1::Error>> {
+        self.controllers.set_draw_area(start, end)
+    }
+
+    /// Send a raw buffer to the selected draw area, continuing after the previous call.
+    pub fn draw(&mut self, buffer: &[u8]) -> Result<(), Error<CS1::Error>> {
+        self.controllers.draw(buffer)
     }
 }
@@ -0,0 +1,21 @@
+//! Display modes.
+
+pub use crate::buffered_graphics::BufferedGraphicsMode;
+pub use crate::mono_graphics::BufferedMonoMode;
+
+/// Mode of a display which has not been initialised yet, only reset and init are available
+#[derive(Copy, Clone, Debug)]
+pub struct UninitializedMode;
+
+/// Raw mode, the display RAM is written directly with `set_draw_area` and `draw`
+#[derive(Copy, Clone, Debug)]
+pub struct RawMode;
+
+/// Marker trait for the modes of an initialised display
+pub trait InitializedMode {
+    /// Forget what the mode knows about the display RAM content after the image is
+    /// invalidated, for example by a rotation change, so the next flush sends all of it
+    fn invalidate(&mut self) {}
+}
+
+impl InitializedMode for RawMode {}
@@ -0,0 +1,273 @@
+//! Monochrome buffered graphics mode.
+
+use crate::{
+    buffered_graphics::DirtyArea,
+    controllers::Frame,
+    error::Error,
+    mode::{InitializedMode, RawMode},
+    size::{DisplaySize, DisplaySize320x132, DualDisplaySize, NewZeroed},
+    Ssd1320z2,
+};
+
+use core::{cmp::min, convert::TryInto, marker::PhantomData};
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::OutputPin;
+
+use embedded_graphics_core::{
+    pixelcolor::{BinaryColor, Gray4, GrayColor},
+    prelude::*,
+};
+
+/// Number of expanded bytes sent to the display at once by `flush`
+const CHUNK_LEN: usize = 40;
+
+/// Monochrome buffered graphics mode, drawing happens into a 1 bit per pixel framebuffer
+/// which is expanded to the off and on gray levels by `flush`
+///
+/// Like the Gray4 [`BufferedGraphicsMode`](crate::mode::BufferedGraphicsMode), the mode
+/// sends only the areas drawn since the last `flush` for each controller. The framebuffer
+/// is stored in `BUFFER`, by default an array owned by the mode.
+#[derive(Debug)]
+pub struct BufferedMonoMode<SIZE, BUFFER = <SIZE as DualDisplaySize>::MonoBuffer>
+where
+    SIZE: DualDisplaySize,
+{
+    buffer: BUFFER,
+    dirty: DirtyArea,
+    levels: (Gray4, Gray4),
+    size: PhantomData<SIZE>,
+}
+
+impl<SIZE, BUFFER> BufferedMonoMode<SIZE, BUFFER>
+where
+    SIZE: DualDisplaySize,
+    BUFFER: AsMut<[u8]>,
+{
+    /// Length of the framebuffer in bytes
+    const LEN: usize = SIZE::WIDTH as usize * SIZE::HEIGHT as usize / 8;
+
+    /// Create a new monochrome mode instance using the given storage of at least
+    /// [`LEN`](Self::LEN) bytes, the framebuffer is cleared and the whole of it is sent by the
+    /// first `flush`
+    fn with_buffer(mut buffer: BUFFER) -> Self {
+        buffer.as_mut()[..Self::LEN].fill(0);
+
+        Self {
+            buffer,
+            dirty: DirtyArea::full::<SIZE>(),
+            levels: (Gray4::BLACK, Gray4::WHITE),
+            size: PhantomData,
+        }
+    }
+}
+
+impl<SIZE, BUFFER> InitializedMode for BufferedMonoMode<SIZE, BUFFER>
+where
+    SIZE: DualDisplaySize,
+{
+    fn invalidate(&mut self) {
+        self.dirty = DirtyArea::full::<SIZE>();
+    }
+}
+
+/// SSD1320z2 driver in the monochrome buffered graphics mode
+pub type BufferedSsd1320z2Mono<
+    DI,
+    CS1,
+    CS2,
+    SIZE = DisplaySize320x132,
+    BUFFER = <SIZE as DualDisplaySize>::MonoBuffer,
+> = Ssd1320z2<DI, CS1, CS2, SIZE, BufferedMonoMode<SIZE, BUFFER>>;
+
+impl<DI, CS1, CS2, SIZE> Ssd1320z2<DI, CS1, CS2, SIZE, RawMode>
+where
+    SIZE: DualDisplaySize,
+{
+    /// Convert the display into the monochrome buffered graphics mode with a cleared
+    /// framebuffer, off pixels are black and on pixels are white.
+    pub fn into_buffered_mono_mode(self) -> BufferedSsd1320z2Mono<DI, CS1, CS2, SIZE> {
+        self.into_mode(BufferedMonoMode::with_buffer(NewZeroed::new_zeroed()))
+    }
+
+    /// Convert the display into the monochrome buffered graphics mode drawing into the
+    /// given storage, which is cleared.
+    ///
+    /// A storage shorter than `SIZE::WIDTH * SIZE::HEIGHT / 8` bytes is rejected with
+    /// [`Error::InvalidParameter`], the display is returned with the error.
+    #[allow(clippy::type_complexity)]
+    pub fn into_buffered_mono_mode_with_buffer<BUFFER>(
+        self,
+        buffer: BUFFER,
+    ) -> Result<BufferedSsd1320z2Mono<DI, CS1, CS2, SIZE, BUFFER>, (Error<CS1::Error>, Self)>
+    where
+        CS1: OutputPin,
+        BUFFER: AsMut<[u8]> + AsRef<[u8]>,
+    {
+        if buffer.as_ref().len() < BufferedMonoMode::<SIZE, BUFFER>::LEN {
+            return Err((Error::InvalidParameter("framebuffer length"), self));
+        }
+
+        Ok(self.into_mode(BufferedMonoMode::with_buffer(buffer)))
+    }
+}
+
+impl<DI, CS1, CS2, SIZE, BUFFER> BufferedSsd1320z2Mono<DI, CS1, CS2, SIZE, BUFFER>
+where
+    SIZE: DualDisplaySize,
+{
+    /// Convert the display into the raw mode, the framebuffer is dropped.
+    pub fn into_raw_mode(self) -> Ssd1320z2<DI, CS1, CS2, SIZE, RawMode> {
+        self.into_raw_mode_and_buffer().0
+    }
+
+    /// Convert the display into the raw mode and return the framebuffer storage.
+    pub fn into_raw_mode_and_buffer(self) -> (Ssd1320z2<DI, CS1, CS2, SIZE, RawMode>, BUFFER) {
+        let Ssd1320z2 {
+            controllers,
+            scroll_area,
+            zoom,
+            mode,
+        } = self;
+        let display = Ssd1320z2 {
+            controllers,
+            scroll_area,
+            zoom,
+            mode: RawMode,
+        };
+
+        (display, mode.buffer)
+    }
+
+    /// Get the gray levels of the off and on pixels.
+    pub fn levels(&self) -> (Gray4, Gray4) {
+        self.mode.levels
+    }
+
+    /// Set the gray levels of the off and on pixels, the whole framebuffer is sent by the
+    /// next `flush`.
+    pub fn set_levels(&mut self, off: Gray4, on: Gray4) {
+        self.mode.levels = (off, on);
+        self.mode.dirty = DirtyArea::full::<SIZE>();
+    }
+}
+
+impl<DI, CS1, CS2, SIZE, BUFFER> BufferedSsd1320z2Mono<DI, CS1, CS2, SIZE, BUFFER>
+where
+    DI: WriteOnlyDataCommand,
+    CS1: OutputPin,
+    CS2: OutputPin<Error = CS1::Error>,
+    SIZE: DualDisplaySize,
+    BUFFER: AsRef<[u8]>,
+{
+    /// Updates the display from the framebuffer.
+    ///
+    /// The pixels are expanded to packed Gray4 bytes while streaming, only the areas drawn
+    /// since the previous flush are sent, and only the upper half of the framebuffer in zoom
+    /// in mode.
+    pub fn flush(&mut self) -> Result<(), Error<CS1::Error>> {
+        let height = self.canvas_height();
+        let Ssd1320z2 {
+            controllers, mode, ..
+        } = self;
+        // Packed byte of every combination of the two pixels of a display RAM column
+        let (off, on) = (mode.levels.0.luma(), mode.levels.1.luma());
+        let bytes = [off | off << 4, on | off << 4, off | on << 4, on | on << 4];
+        let buffer = mode.buffer.as_ref();
+        let mut chunk = [0; CHUNK_LEN];
+        for &Frame { start, end } in mode.dirty.take(height, SIZE::Half::WIDTH).iter().flatten() {
+            controllers.set_draw_area(start, end)?;
+            for y in start.1..=end.1 {
+                let row = y as usize * SIZE::WIDTH as usize;
+                let mut x = start.0 as usize;
+                while x < end.0 as usize {
+                    let len = min(CHUNK_LEN, (end.0 as usize + 1 - x) / 2);
+                    for byte in chunk[..len].iter_mut() {
+                        // Both pixels are in one framebuffer byte, as `x` is even
+                        let bit = row + x;
+                        *byte = bytes[(buffer[bit / 8] >> (bit % 8)) as usize & 0b11];
+                        x += 2;
+                    }
+                    controllers.draw(&chunk[..len])?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<DI, CS1, CS2, SIZE, BUFFER> OriginDimensions
+    for BufferedSsd1320z2Mono<DI, CS1, CS2, SIZE, BUFFER>
+where
+    SIZE: DualDisplaySize,
+{
+    fn size(&self) -> Size {
+        let height = self.canvas_height() as u32;
+        if self.rotation().is_portrait() {
+            Size::new(height, SIZE::WIDTH as u32)
+        } else {
+            Size::new(SIZE::WIDTH as u32, height)
+        }
+    }
+}
+
+impl<DI, CS1, CS2, SIZE, BUFFER> DrawTarget for BufferedSsd1320z2Mono<DI, CS1, CS2, SIZE, BUFFER>
+where
+    CS1: OutputPin,
+    SIZE: DualDisplaySize,
+    BUFFER: AsMut<[u8]>,
+{
+    type Color = BinaryColor;
+
+    type Error = Error<CS1::Error>;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let width = SIZE::WIDTH as u32;
+        let height = self.canvas_height() as u32;
+        let portrait = self.rotation().is_portrait();
+        let buffer = self.mode.buffer.as_mut();
+
+        for Pixel(point, color) in pixels.into_iter() {
+            // Same turn of the portrait image as in the Gray4 mode
+            let point = if portrait {
+                Point::new(width as i32 - 1 - point.y, point.x)
+            } else {
+                point
+            };
+            // Out of bounds pixels are discarded
+            if let Ok((x, y)) = point.try_into() {
+                let (x, y): (u32, u32) = (x, y);
+                if x >= width || y >= height {
+                    continue;
+                }
+                let bit = (x + y * width) as usize;
+                if color.is_on() {
+                    buffer[bit / 8] |= 1 << (bit % 8);
+                } else {
+                    buffer[bit / 8] &= !(1 << (bit % 8));
+                }
+                let (x, y) = (x as u16, y as u16);
+                let half = usize::from(x >= SIZE::Half::WIDTH);
+                self.mode.dirty.add(half, x, y);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        let height = self.canvas_height();
+        let len = SIZE::WIDTH as usize * height as usize / 8;
+        let byte = if color.is_on() { 0xff } else { 0x00 };
+        self.mode.buffer.as_mut()[..len].fill(byte);
+        self.mode
+            .dirty
+            .add_frame(Frame::new(SIZE::WIDTH, height), SIZE::Half::WIDTH);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,428 @@
+//! Drawing without a framebuffer in the raw mode.
+//!
+//! The display RAM cannot be read back and every byte holds a column of two pixels, so a
+//! column written with a single pixel gets the [`PADDING`] level in the other one. In the
+//! portrait rotations a column holds two adjacent rows of the image.
+
+use core::{convert::TryInto, iter};
+
+use display_interface::WriteOnlyDataCommand;
+use embedded_hal::digital::v2::OutputPin;
+
+use embedded_graphics_core::{
+    pixelcolor::{Gray4, GrayColor},
+    prelude::*,
+    primitives::Rectangle,
+};
+
+use crate::{
+    controllers::{Controllers, Frame},
+    error::Error,
+    mode::RawMode,
+    size::DualDisplaySize,
+    Ssd1320z2,
+};
+
+/// Gray level of the other pixel of a display RAM column written with a single pixel
+const PADDING: u8 = 0;
+
+/// Number of packed bytes sent to the display at once
+const CHUNK_LEN: usize = 40;
+
+/// Longest row of a portrait image, the height of the tallest display size
+const PORTRAIT_ROW_LEN: usize = 160;
+
+/// Packed bytes collected into chunks before they are sent to the draw area
+struct Stream<'a, DI, CS1, CS2, SIZE>
+where
+    SIZE: DualDisplaySize,
+{
+    controllers: &'a mut Controllers<DI, CS1, CS2, SIZE>,
+    chunk: [u8; CHUNK_LEN],
+    len: usize,
+}
+
+impl<'a, DI, CS1, CS2, SIZE> Stream<'a, DI, CS1, CS2, SIZE>
+where
+    DI: WriteOnlyDataCommand,
+    CS1: OutputPin,
+    CS2: OutputPin<Error = CS1::Error>,
+    SIZE: DualDisplaySize,
+{
+    /// Set up the draw area of a frame with even start and odd end columns
+    fn new(
+        controllers: &'a mut Controllers<DI, CS1, CS2, SIZE>,
+        frame: Frame,
+    ) -> Result<Self, Error<CS1::Error>> {
+        controllers.set_draw_area(frame.start, frame.end)?;
+
+        Ok(Self {
+            controllers,
+            chunk: [0; CHUNK_LEN],
+            len: 0,
+        })
+    }
+
+    fn push(&mut self, byte: u8) -> Result<(), Error<CS1::Error>> {
+        self.chunk[self.len] = byte;
+        self.len += 1;
+        if self.len == CHUNK_LEN {
+            self.finish()?;
+        }
+
+        Ok(())
+    }
+
+    /// Send the collected bytes
+    fn finish(&mut self) -> Result<(), Error<CS1::Error>> {
+        let len = self.len;
+        self.len = 0;
+        if len == 0 {
+            return Ok(());
+        }
+        self.controllers.draw(&self.chunk[..len])
+    }
+}
+
+impl<DI, CS1, CS2, SIZE> Ssd1320z2<DI, CS1, CS2, SIZE, RawMode>
+where
+    DI: WriteOnlyDataCommand,
+    CS1: OutputPin,
+    CS2: OutputPin<Error = CS1::Error>,
+    SIZE: DualDisplaySize,
+{
+    /// Landscape frame of the visible part of an area, or `None` if nothing is visible or
+    /// the display is rotated to portrait
+    fn landscape_frame(&self, area: &Rectangle) -> Option<Frame> {
+        if self.rotation().is_portrait() {
+            return None;
+        }
+        let visible = area.intersection(&self.bounding_box());
+        let bottom_right = visible.bottom_right()?;
+
+        Some(Frame {
+            start: (visible.top_left.x as u16, visible.top_left.y as u16),
+            end: (bottom_right.x as u16, bottom_right.y as u16),
+        })
+    }
+
+    /// Write a single display RAM column
+    fn draw_column(&mut self, x: u16, y: u16, byte: u8) -> Result<(), Error<CS1::Error>> {
+        self.controllers.set_draw_area((x, y), (x + 1, y))?;
+        self.controllers.draw(&[byte])
+    }
+
+    /// Write the packed columns of a pair of portrait rows, starting at the even `row` and
+    /// the portrait column `left`
+    fn draw_row_pair(
+        &mut self,
+        row: i32,
+        left: i32,
+        columns: &[u8],
+    ) -> Result<(), Error<CS1::Error>> {
+        // Same turn of the portrait image as in the buffered graphics mode
+        let x = SIZE::WIDTH - 2 - row as u16;
+        let y = left as u16;
+        self.controllers
+            .set_draw_area((x, y), (x + 1, y + columns.len() as u16 - 1))?;
+        self.controllers.draw(columns)
+    }
+
+    /// Write the bytes of a pair of landscape columns starting at the even `x` marked in
+    /// `drawn`, each run of them with one draw area
+    fn draw_column_pair(
+        &mut self,
+        x: u16,
+        columns: &[u8],
+        drawn: &[bool],
+    ) -> Result<(), Error<CS1::Error>> {
+        let mut y = 0;
+        while y < drawn.len() {
+            if !drawn[y] {
+                y += 1;
+                continue;
+            }
+            let end = drawn[y..]
+                .iter()
+                .position(|&drawn| !drawn)
+                .map_or(drawn.len(), |len| y + len);
+            self.controllers
+                .set_draw_area((x, y as u16), (x + 1, end as u16 - 1))?;
+            self.controllers.draw(&columns[y..end])?;
+            y = end;
+        }
+
+        Ok(())
+    }
+
+    /// Draw the pixels of a portrait image, buffering the columns of the pair of rows they
+    /// are in until the pixels move to another pair, so both rows of a column are kept
+    fn draw_portrait<I>(&mut self, pixels: I) -> Result<(), Error<CS1::Error>>
+    where
+        I: IntoIterator<Item = Pixel<Gray4>>,
+    {
+        let width = SIZE::WIDTH as i32;
+        let height = self.canvas_height() as usize;
+        let mut columns = [PADDING | PADDING << 4; PORTRAIT_ROW_LEN];
+        let mut drawn = [false; PORTRAIT_ROW_LEN];
+        // Even landscape column of the buffered pair
+        let mut pair: Option<u16> = None;
+
+        for Pixel(point, color) in pixels.into_iter() {
+            // Same turn of the portrait image as in the buffered graphics mode, out of bounds
+            // pixels are discarded
+            let (x, y) = (width - 1 - point.y, point.x);
+            if x < 0 || x >= width || y < 0 || y as usize >= height {
+                continue;
+            }
+            let column = x as u16 & !1;
+            if pair != Some(column) {
+                if let Some(x) = pair {
+                    self.draw_column_pair(x, &columns[..height], &drawn[..height])?;
+                }
+                columns = [PADDING | PADDING << 4; PORTRAIT_ROW_LEN];
+                drawn = [false; PORTRAIT_ROW_LEN];
+                pair = Some(column);
+            }
+            let index = y as usize;
+            let shift = if x % 2 == 0 { 0 } else { 4 };
+            columns[index] = columns[index] & !(0xf << shift) | color.luma() << shift;
+            drawn[index] = true;
+        }
+        if let Some(x) = pair {
+            self.draw_column_pair(x, &columns[..height], &drawn[..height])?;
+        }
+
+        Ok(())
+    }
+
+    /// Fill a portrait area, buffering the columns of two rows at a time
+    fn fill_portrait<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Error<CS1::Error>>
+    where
+        I: IntoIterator<Item = Gray4>,
+    {
+        let visible = area.intersection(&self.bounding_box());
+        let left = visible.top_left.x;
+        let len = visible.size.width as usize;
+        if visible.is_zero_sized() || len > PORTRAIT_ROW_LEN {
+            return Ok(());
+        }
+        let mut columns = [PADDING | PADDING << 4; PORTRAIT_ROW_LEN];
+        // Even row of the buffered pair and the number of columns reached in it
+        let mut pair: Option<(i32, usize)> = None;
+
+        for (point, color) in area.points().zip(colors) {
+            if !visible.contains(point) {
+                continue;
+            }
+            let row = point.y & !1;
+            let filled = match pair {
+                Some((pair_row, filled)) if pair_row == row => filled,
+                _ => {
+                    if let Some((pair_row, filled)) = pair {
+                        self.draw_row_pair(pair_row, left, &columns[..filled])?;
+                    }
+                    columns = [PADDING | PADDING << 4; PORTRAIT_ROW_LEN];
+                    0
+                }
+            };
+            let index = (point.x - left) as usize;
+            // Even rows land on odd landscape columns, the high nibble
+            let shift = if point.y % 2 == 0 { 4 } else { 0 };
+            columns[index] = columns[index] & !(0xf << shift) | color.luma() << shift;
+            pair = Some((row, filled.max(index + 1)));
+        }
+        if let Some((row, filled)) = pair {
+            self.draw_row_pair(row, left, &columns[..filled])?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<DI, CS1, CS2, SIZE> OriginDimensions for Ssd1320z2<DI, CS1, CS2, SIZE, RawMode>
+where
+    SIZE: DualDisplaySize,
+{
+    fn size(&self) -> Size {
+        let height = self.canvas_height() as u32;
+        if self.rotation().is_portrait() {
+            Size::new(height, SIZE::WIDTH as u32)
+        } else {
+            Size::new(SIZE::WIDTH as u32, height)
+        }
+    }
+}
+
+impl<DI, CS1, CS2, SIZE> DrawTarget for Ssd1320z2<DI, CS1, CS2, SIZE, RawMode>
+where
+    DI: WriteOnlyDataCommand,
+    CS1: OutputPin,
+    CS2: OutputPin<Error = CS1::Error>,
+    SIZE: DualDisplaySize,
+{
+    type Color = Gray4;
+
+    type Error = Error<CS1::Error>;
+
+    /// Every pixel is written with its own draw area, consecutive pixels of one display RAM
+    /// column are written together. In the portrait rotations the columns of a pair of
+    /// image rows are collected until the pixels move to another pair of rows.
+    ///
+    /// A display RAM column written with a single pixel gets black padding in the other one,
+    /// which erases what was drawn there before: a vertical line at x = 5 drawn after the one
+    /// at x = 4 blackens it, and a 1 pixel outline drawn over a filled rectangle leaves black
+    /// pixels beside the stroke. Draw such images in the buffered graphics mode.
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        if self.rotation().is_portrait() {
+            return self.draw_portrait(pixels);
+        }
+        let width = SIZE::WIDTH as u32;
+        let height = self.canvas_height() as u32;
+        // Column waiting for the other pixel, with its even x, y and packed byte
+        let mut pending: Option<(u16, u16, u8)> = None;
+
+        for Pixel(point, color) in pixels.into_iter() {
+            // Out of bounds pixels are discarded
+            if let Ok((x, y)) = point.try_into() {
+                let (x, y): (u32, u32) = (x, y);
+                if x >= width || y >= height {
+                    continue;
+                }
+                let (column, y) = (x as u16 & !1, y as u16);
+                let shift = if x % 2 == 0 { 0 } else { 4 };
+                let byte = match pending {
+                    Some((x, pending_y, byte)) if (x, pending_y) == (column, y) => byte,
+                    _ => {
+                        if let Some((x, y, byte)) = pending {
+                            self.draw_column(x, y, byte)?;
+                        }
+                        PADDING | PADDING << 4
+                    }
+                };
+                let byte = byte & !(0xf << shift) | color.luma() << shift;
+                pending = Some((column, y, byte));
+            }
+        }
+        if let Some((x, y, byte)) = pending {
+            self.draw_column(x, y, byte)?;
+        }
+
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let frame = match self.landscape_frame(area) {
+            Some(frame) => frame,
+            None if self.rotation().is_portrait() => return self.fill_portrait(area, colors),
+            None => return Ok(()),
+        };
+        let Frame { start, end } = frame;
+        let area_width = area.size.width as usize;
+        let skip_left = (start.0 as i32 - area.top_left.x) as usize;
+        let skip_right = area_width - skip_left - (end.0 - start.0 + 1) as usize;
+        let mut colors = colors.into_iter().map(|color| color.luma());
+        let window = Frame {
+            start: (start.0 & !1, start.1),
+            end: (end.0 | 1, end.1),
+        };
+        let mut stream = Stream::new(&mut self.controllers, window)?;
+
+        let skip_top = (start.1 as i32 - area.top_left.y) as usize * area_width;
+        if skip_top > 0 && colors.nth(skip_top - 1).is_none() {
+            return Ok(());
+        }
+        'rows: for _ in start.1..=end.1 {
+            if skip_left > 0 && colors.nth(skip_left - 1).is_none() {
+                break;
+            }
+            let mut x = start.0;
+            if x % 2 == 1 {
+                match colors.next() {
+                    Some(luma) => stream.push(PADDING | luma << 4)?,
+                    None => break,
+                }
+                x += 1;
+            }
+            while x <= end.0 {
+                let low = match colors.next() {
+                    Some(luma) => luma,
+                    None => break 'rows,
+                };
+                // The pixel after an even end is padding
+                let high = if x == end.0 {
+                    PADDING
+                } else {
+                    match colors.next() {
+                        Some(luma) => luma,
+                        None => {
+                            stream.push(low | PADDING << 4)?;
+                            break 'rows;
+                        }
+                    }
+                };
+                stream.push(low | high << 4)?;
+                x += 2;
+            }
+            if skip_right > 0 && colors.nth(skip_right - 1).is_none() {
+                break;
+            }
+        }
+
+        stream.finish()
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let frame = match self.landscape_frame(area) {
+            Some(frame) => frame,
+            None if self.rotation().is_portrait() => {
+                let len = area.size.width as usize * area.size.height as usize;
+                return self.fill_portrait(area, iter::repeat(color).take(len));
+            }
+            None => return Ok(()),
+        };
+        let Frame { start, end } = frame;
+        let luma = color.luma();
+        let window = Frame {
+            start: (start.0 & !1, start.1),
+            end: (end.0 | 1, end.1),
+        };
+        let row_len = ((window.end.0 - window.start.0 + 1) / 2) as usize;
+        let mut stream = Stream::new(&mut self.controllers, window)?;
+
+        for _ in start.1..=end.1 {
+            for index in 0..row_len {
+                let low = if index == 0 && start.0 % 2 == 1 {
+                    PADDING
+                } else {
+                    luma
+                };
+                let high = if index == row_len - 1 && end.0 % 2 == 0 {
+                    PADDING
+                } else {
+                    luma
+                };
+                stream.push(low | high << 4)?;
+            }
+        }
+
+        stream.finish()
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        let luma = color.luma();
+        let frame = Frame::new(SIZE::WIDTH, SIZE::HEIGHT);
+        let mut stream = Stream::new(&mut self.controllers, frame)?;
+        for _ in 0..SIZE::WIDTH as usize / 2 * SIZE::HEIGHT as usize {
+            stream.push(luma | luma << 4)?;
+        }
+
+        stream.finish()
+    }
+}
@@ -0,0 +1,34 @@
+//! Display rotation.
+
+/// Display rotation.
+///
+/// Rotations are clockwise. 180 degrees rotation is done by the controllers, 90 and 270
+/// degrees rotations are done by the buffered graphics mode while drawing into the
+/// framebuffer, raw `draw` calls stay in the landscape orientation.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum DisplayRotation {
+    /// No rotation, normal display
+    #[default]
+    Rotate0,
+    /// Rotate by 90 degrees clockwise
+    Rotate90,
+    /// Rotate by 180 degrees clockwise
+    Rotate180,
+    /// Rotate 270 degrees clockwise
+    Rotate270,
+}
+
+impl DisplayRotation {
+    /// The image is turned upside down by the controllers.
+    pub(crate) fn is_flipped(self) -> bool {
+        matches!(
+            self,
+            DisplayRotation::Rotate180 | DisplayRotation::Rotate270
+        )
+    }
+
+    /// Width and height are swapped.
+    pub(crate) fn is_portrait(self) -> bool {
+        matches!(self, DisplayRotation::Rotate90 | DisplayRotation::Rotate270)
+    }
+}
@@ -0,0 +1,159 @@
+//! Display size.
+
+use core::fmt::Debug;
+
+/// Workaround trait, since `Default` is only implemented to arrays up to 32 of size
+pub trait NewZeroed {
+    /// Creates a new value with its memory set to zero
+    fn new_zeroed() -> Self;
+}
+
+impl<const N: usize> NewZeroed for [u8; N] {
+    fn new_zeroed() -> Self {
+        [0u8; N]
+    }
+}
+
+/// Display information
+///
+/// This trait describes information related to a particular display glass.
+/// This includes resolution, offsets and framebuffer size.
+pub trait DisplaySize {
+    /// Width in pixels
+    const WIDTH: u16;
+
+    /// Height in pixels, also used as multiplex ratio
+    const HEIGHT: u16;
+
+    /// Horizontal offset in pixels of the first connected segment, must be even
+    const OFFSETX: u8 = 0;
+
+    /// Vertical shift by COM applied with the display offset command
+    const OFFSETY: u8 = 0;
+
+    /// Size of framebuffer. Because the display is 4 bits per pixel, this is
+    /// `WIDTH * HEIGHT / 2`
+    type Buffer: AsMut<[u8]> + AsRef<[u8]> + NewZeroed;
+}
+
+/// Display information for modules made of two SSD1320 controllers placed side by side
+///
+/// `WIDTH` and `HEIGHT` describe the whole module, each controller drives a half
+/// of the columns described by [`DualDisplaySize::Half`].
+pub trait DualDisplaySize: DisplaySize {
+    /// Geometry driven by each of the two controllers
+    type Half: DisplaySize + Copy + Debug + Default;
+
+    /// Size of the 1 bit per pixel framebuffer of the monochrome mode, this is
+    /// `WIDTH * HEIGHT / 8`
+    type MonoBuffer: AsMut<[u8]> + AsRef<[u8]> + NewZeroed;
+}
+
+/// Size information for the common 160x160 variants
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DisplaySize160x160;
+impl DisplaySize for DisplaySize160x160 {
+    const WIDTH: u16 = 160;
+    const HEIGHT: u16 = 160;
+    type Buffer = [u8; Self::WIDTH as usize * Self::HEIGHT as usize / 2];
+}
+
+/// Size information for the 160x132 variants
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DisplaySize160x132;
+impl DisplaySize for DisplaySize160x132 {
+    const WIDTH: u16 = 160;
+    const HEIGHT: u16 = 132;
+    type Buffer = [u8; Self::WIDTH as usize * Self::HEIGHT as usize / 2];
+}
+
+/// Size information for the 160x128 variants
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DisplaySize160x128;
+impl DisplaySize for DisplaySize160x128 {
+    const WIDTH: u16 = 160;
+    const HEIGHT: u16 = 128;
+    type Buffer = [u8; Self::WIDTH as usize * Self::HEIGHT as usize / 2];
+}
+
+/// Size information for the 160x80 variants
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DisplaySize160x80;
+impl DisplaySize for DisplaySize160x80 {
+    const WIDTH: u16 = 160;
+    const HEIGHT: u16 = 80;
+    type Buffer = [u8; Self::WIDTH as usize * Self::HEIGHT as usize / 2];
+}
+
+/// Size information for the 96x96 variants
+///
+/// The glass is expected to be connected to the middle segments of the controller.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DisplaySize96x96;
+impl DisplaySize for DisplaySize96x96 {
+    const WIDTH: u16 = 96;
+    const HEIGHT: u16 = 96;
+    const OFFSETX: u8 = 32;
+    type Buffer = [u8; Self::WIDTH as usize * Self::HEIGHT as usize / 2];
+}
+
+/// Size information for the Surenoo SUR383S1000WG01 (320x132) dual controller module
+///
+/// The glass rows are centered on the COM lines of both controllers.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DisplaySize320x132;
+impl DisplaySize for DisplaySize320x132 {
+    const WIDTH: u16 = 320;
+    const HEIGHT: u16 = 132;
+    const OFFSETY: u8 = 0x0e;
+    type Buffer = [u8; Self::WIDTH as usize * Self::HEIGHT as usize / 2];
+}
+impl DualDisplaySize for DisplaySize320x132 {
+    type Half = DisplaySize160x132;
+    type MonoBuffer = [u8; Self::WIDTH as usize * Self::HEIGHT as usize / 8];
+}
+
+/// Size information for the 320x160 dual controller modules
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DisplaySize320x160;
+impl DisplaySize for DisplaySize320x160 {
+    const WIDTH: u16 = 320;
+    const HEIGHT: u16 = 160;
+    type Buffer = [u8; Self::WIDTH as usize * Self::HEIGHT as usize / 2];
+}
+impl DualDisplaySize for DisplaySize320x160 {
+    type Half = DisplaySize160x160;
+    type MonoBuffer = [u8; Self::WIDTH as usize * Self::HEIGHT as usize / 8];
+}
+
+/// Size information for the 320x128 dual controller modules
+///
+/// The glass rows are centered on the COM lines of both controllers.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DisplaySize320x128;
+impl DisplaySize for DisplaySize320x128 {
+    const WIDTH: u16 = 320;
+    const HEIGHT: u16 = 128;
+    const OFFSETY: u8 = 0x10;
+    type Buffer = [u8; Self::WIDTH as usize * Self::HEIGHT as usize / 2];
+}
+impl DualDisplaySize for DisplaySize320x128 {
+    type Half = DisplaySize160x128;
+    type MonoBuffer = [u8; Self::WIDTH as usize * Self::HEIGHT as usize / 8];
+}
+
+/// Size information for the 320x80 dual controller modules
+///
+/// The glass rows are centered on the COM lines of both controllers.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DisplaySize320x80;
+impl DisplaySize for DisplaySize320x80 {
+    const WIDTH: u16 = 320;
+    const HEIGHT: u16 = 80;
+    const OFFSETY: u8 = 0x28;
+    type Buffer = [u8; Self::WIDTH as usize * Self::HEIGHT as usize / 2];
+}
+impl DualDisplaySize for DisplaySize320x80 {
+    type Half = DisplaySize160x80;
+    type MonoBuffer = [u8; Self::WIDTH as usize * Self::HEIGHT as usize / 8];
+}
@@ -0,0 +1,1083 @@
+use display_interface::{DataFormat::U8, WriteOnlyDataCommand};
+use embedded_graphics_core::{
+    pixelcolor::{BinaryColor, Gray4},
+    prelude::*,
+    primitives::Rectangle,
+};
+
+use ssd1320::{
+    command::{AddrMode, Command, PortraitAddrMode},
+    config::Config,
+    emulator::{Ssd1320Emulator, Ssd1320z2Emulator},
+    error::Error,
+    rotation::DisplayRotation,
+    size::{DisplaySize160x160, DisplaySize320x132, DisplaySize320x160},
+    ScrollDirection, ScrollInterval, Ssd1320, Ssd1320z2,
+};
+
+const WIDTH: u16 = 320;
+const HEIGHT: u16 = 132;
+
+/// Gray level of the test pattern at a pixel
+fn pattern(x: u16, y: u16) -> u8 {
+    ((x * 3 + y * 5) % 16) as u8
+}
+
+fn pattern_pixels() -> impl Iterator<Item = Pixel<Gray4>> {
+    (0..HEIGHT).flat_map(|y| {
+        (0..WIDTH).map(move |x| Pixel(Point::new(x as i32, y as i32), Gray4::new(pattern(x, y))))
+    })
+}
+
+fn new_display(
+    emulator: &Ssd1320z2Emulator<DisplaySize320x132>,
+    rotation: DisplayRotation,
+) -> Ssd1320z2<
+    ssd1320::emulator::EmulatedInterface,
+    ssd1320::emulator::EmulatedChipSelect,
+    ssd1320::emulator::EmulatedChipSelect,
+> {
+    Ssd1320z2::new(
+        emulator.interface(),
+        emulator.cs1(),
+        emulator.cs2(),
+        DisplaySize320x132,
+        rotation,
+    )
+    .init(&Config::default())
+    .unwrap()
+}
+
+#[test]
+fn init_sends_the_same_sequence_to_both_controllers_except_orientation() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    new_display(&emulator, DisplayRotation::Rotate0);
+
+    let one = emulator.controller(0);
+    let two = emulator.controller(1);
+    assert_eq!(one.commands().len(), two.commands().len());
+    assert!(one.commands().contains(&Command::DisplayOffset(0x0e)));
+    assert!(two.commands().contains(&Command::DisplayOffset(160 - 0x0e)));
+    assert!(one.commands().contains(&Command::ReverseComDir(true)));
+    assert!(two.commands().contains(&Command::SegmentRemap(true)));
+    assert_eq!(one.commands().last(), Some(&Command::DisplayOn(true)));
+}
+
+#[test]
+fn draw_splits_a_window_crossing_the_middle() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display = new_display(&emulator, DisplayRotation::Rotate0);
+
+    // 20 pixels wide window, 10 on each controller, three rows
+    let (start, end) = ((150, 10), (169, 12));
+    display.set_draw_area(start, end).unwrap();
+    let bytes: Vec<u8> = (0..30u8).map(|i| i | (i % 16) << 4).collect();
+    display.draw(&bytes[..7]).unwrap();
+    display.draw(&bytes[7..]).unwrap();
+
+    for (i, byte) in bytes.iter().enumerate() {
+        let x = start.0 + (i as u16 % 10) * 2;
+        let y = start.1 + i as u16 / 10;
+        assert_eq!(emulator.pixel(x, y), byte & 0xf, "({}, {})", x, y);
+        assert_eq!(emulator.pixel(x + 1, y), byte >> 4, "({}, {})", x + 1, y);
+    }
+    assert_eq!(emulator.pixel(149, 10), 0);
+    assert_eq!(emulator.pixel(170, 12), 0);
+}
+
+#[test]
+fn draw_with_odd_edges_crossing_the_middle_takes_whole_columns() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display = new_display(&emulator, DisplayRotation::Rotate0);
+
+    // Columns of pixels 150-159 on the first controller and 160-169 on the second one
+    display.set_draw_area((151, 10), (168, 11)).unwrap();
+    let bytes: Vec<u8> = (0..20u8).map(|i| (i % 16) | ((15 - i % 16) << 4)).collect();
+    display.draw(&bytes[..3]).unwrap();
+    display.draw(&bytes[3..13]).unwrap();
+    display.draw(&bytes[13..]).unwrap();
+
+    for (i, byte) in bytes.iter().enumerate() {
+        let x = 150 + (i as u16 % 10) * 2;
+        let y = 10 + i as u16 / 10;
+        assert_eq!(emulator.pixel(x, y), byte & 0xf, "({}, {})", x, y);
+        assert_eq!(emulator.pixel(x + 1, y), byte >> 4, "({}, {})", x + 1, y);
+    }
+}
+
+#[test]
+fn draw_on_one_half_only_selects_its_controller() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display = new_display(&emulator, DisplayRotation::Rotate0);
+
+    display.set_draw_area((300, 131), (301, 131)).unwrap();
+    display.draw(&[0x21]).unwrap();
+
+    assert_eq!(emulator.pixel(300, 131), 0x1);
+    assert_eq!(emulator.pixel(301, 131), 0x2);
+    assert_eq!(emulator.controller(0).ram_pixel(140, 131), 0);
+}
+
+#[test]
+fn flush_shows_the_framebuffer() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display =
+        new_display(&emulator, DisplayRotation::Rotate0).into_buffered_graphics_mode();
+
+    display.draw_iter(pattern_pixels()).unwrap();
+    display.flush().unwrap();
+
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            assert_eq!(emulator.pixel(x, y), pattern(x, y), "({}, {})", x, y);
+        }
+    }
+}
+
+/// Data bytes received by both controllers
+fn data_len(emulator: &Ssd1320z2Emulator<DisplaySize320x132>) -> usize {
+    emulator.controller(0).data_len() + emulator.controller(1).data_len()
+}
+
+#[test]
+fn flush_sends_only_the_drawn_area() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display =
+        new_display(&emulator, DisplayRotation::Rotate0).into_buffered_graphics_mode();
+    display.flush().unwrap();
+    assert_eq!(data_len(&emulator), 320 * 132 / 2);
+
+    // Odd edges are widened to whole bytes, three bytes per row
+    let sent = data_len(&emulator);
+    display
+        .draw_iter((11..=14).flat_map(|x| {
+            (20..=22).map(move |y| Pixel(Point::new(x, y), Gray4::new(pattern(x as u16, y as u16))))
+        }))
+        .unwrap();
+    display.flush().unwrap();
+    assert_eq!(data_len(&emulator) - sent, 9);
+    for y in 19..=23 {
+        for x in 10..=15 {
+            let expected = if (11..=14).contains(&x) && (20..=22).contains(&y) {
+                pattern(x, y)
+            } else {
+                0
+            };
+            assert_eq!(emulator.pixel(x, y), expected, "({}, {})", x, y);
+        }
+    }
+
+    // Nothing is sent without drawing
+    let sent = data_len(&emulator);
+    display.flush().unwrap();
+    assert_eq!(data_len(&emulator), sent);
+}
+
+#[test]
+fn flush_splits_the_drawn_area_crossing_the_middle() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display =
+        new_display(&emulator, DisplayRotation::Rotate0).into_buffered_graphics_mode();
+    display.flush().unwrap();
+
+    let sent = data_len(&emulator);
+    display
+        .draw_iter([
+            Pixel(Point::new(157, 40), Gray4::new(3)),
+            Pixel(Point::new(162, 41), Gray4::new(12)),
+        ])
+        .unwrap();
+    display.flush().unwrap();
+
+    // Each half sends its own area, one byte each
+    assert_eq!(data_len(&emulator) - sent, 2);
+    assert_eq!(emulator.pixel(157, 40), 3);
+    assert_eq!(emulator.pixel(162, 41), 12);
+
+    // A rectangle crossing the middle is sent as one window split between the controllers
+    let sent = data_len(&emulator);
+    display
+        .draw_iter((150..170).flat_map(|x| {
+            (10..13).map(move |y| Pixel(Point::new(x, y), Gray4::new(pattern(x as u16, y as u16))))
+        }))
+        .unwrap();
+    display.flush().unwrap();
+    assert_eq!(data_len(&emulator) - sent, 30);
+    for y in 10..13 {
+        for x in 150..170 {
+            assert_eq!(emulator.pixel(x, y), pattern(x, y), "({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn flush_addresses_only_the_changed_controller() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display =
+        new_display(&emulator, DisplayRotation::Rotate0).into_buffered_graphics_mode();
+    display.flush().unwrap();
+
+    let commands = emulator.controller(0).commands().len();
+    let sent = (
+        emulator.controller(0).data_len(),
+        emulator.controller(1).data_len(),
+    );
+    display
+        .draw_iter([Pixel(Point::new(300, 50), Gray4::new(5))])
+        .unwrap();
+    display.flush().unwrap();
+
+    assert_eq!(emulator.controller(0).commands().len(), commands);
+    assert_eq!(emulator.controller(0).data_len(), sent.0);
+    assert_eq!(emulator.controller(1).data_len(), sent.1 + 1);
+    assert_eq!(emulator.pixel(300, 50), 5);
+}
+
+/// Gray level of a point of the fill test areas, which may be outside of the display
+fn area_color(point: Point) -> Gray4 {
+    Gray4::new((point.x * 7 + point.y * 3).rem_euclid(16) as u8)
+}
+
+/// Fill areas with the fast paths on one display and pixel by pixel on another one
+fn assert_fills_match_pixels(rotation: DisplayRotation) {
+    let fast = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let slow = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut fast_display = new_display(&fast, rotation).into_buffered_graphics_mode();
+    let mut slow_display = new_display(&slow, rotation).into_buffered_graphics_mode();
+    fast_display.draw_iter(pattern_pixels()).unwrap();
+    slow_display.draw_iter(pattern_pixels()).unwrap();
+
+    let areas = [
+        Rectangle::new(Point::new(3, 5), Size::new(1, 1)),
+        Rectangle::new(Point::new(4, 20), Size::new(1, 3)),
+        Rectangle::new(Point::new(3, 5), Size::new(2, 7)),
+        Rectangle::new(Point::new(151, 30), Size::new(21, 3)),
+        Rectangle::new(Point::new(-5, -3), Size::new(9, 8)),
+        Rectangle::new(Point::new(313, 125), Size::new(40, 40)),
+        Rectangle::new(Point::new(7, 9), Size::new(0, 5)),
+    ];
+    for (i, area) in areas.iter().enumerate() {
+        let color = Gray4::new(i as u8 + 1);
+        fast_display.fill_solid(area, color).unwrap();
+        slow_display
+            .draw_iter(area.points().map(|point| Pixel(point, color)))
+            .unwrap();
+
+        let area = Rectangle::new(area.top_left + Point::new(11, 40), area.size);
+        fast_display
+            .fill_contiguous(&area, area.points().map(area_color))
+            .unwrap();
+        slow_display
+            .draw_iter(area.points().map(|point| Pixel(point, area_color(point))))
+            .unwrap();
+    }
+
+    // Colors running out stop the fill
+    let area = Rectangle::new(Point::new(41, 90), Size::new(10, 4));
+    let colors = area.points().map(area_color).take(15);
+    fast_display.fill_contiguous(&area, colors).unwrap();
+    slow_display
+        .draw_iter(
+            area.points()
+                .take(15)
+                .map(|point| Pixel(point, area_color(point))),
+        )
+        .unwrap();
+
+    fast_display.flush().unwrap();
+    slow_display.flush().unwrap();
+    assert_eq!(fast.render(), slow.render(), "{:?}", rotation);
+
+    fast_display.clear(Gray4::new(9)).unwrap();
+    fast_display.flush().unwrap();
+    assert!(fast.render().pixels().iter().all(|&level| level == 0x99));
+}
+
+#[test]
+fn fills_match_drawing_pixel_by_pixel() {
+    assert_fills_match_pixels(DisplayRotation::Rotate0);
+    assert_fills_match_pixels(DisplayRotation::Rotate270);
+}
+
+#[test]
+fn fill_solid_flushes_only_the_filled_area() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display =
+        new_display(&emulator, DisplayRotation::Rotate0).into_buffered_graphics_mode();
+    display.flush().unwrap();
+
+    let sent = data_len(&emulator);
+    display
+        .fill_solid(
+            &Rectangle::new(Point::new(201, 7), Size::new(4, 2)),
+            Gray4::WHITE,
+        )
+        .unwrap();
+    display.flush().unwrap();
+
+    assert_eq!(data_len(&emulator) - sent, 6);
+    assert_eq!(emulator.pixel(200, 7), 0);
+    assert_eq!(emulator.pixel(201, 7), 0xf);
+    assert_eq!(emulator.pixel(204, 8), 0xf);
+    assert_eq!(emulator.pixel(205, 8), 0);
+}
+
+#[test]
+fn caller_provided_framebuffer_is_cleared_and_returned() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let buffer: &'static mut [u8] = Box::leak(vec![0xaa; 320 * 132 / 2 + 3].into_boxed_slice());
+    let mut display = new_display(&emulator, DisplayRotation::Rotate0)
+        .into_buffered_graphics_mode_with_buffer(buffer)
+        .ok()
+        .unwrap();
+
+    display
+        .draw_iter([Pixel(Point::new(1, 0), Gray4::new(6))])
+        .unwrap();
+    display.flush().unwrap();
+    assert_eq!(emulator.pixel(0, 0), 0);
+    assert_eq!(emulator.pixel(1, 0), 6);
+    assert_eq!(emulator.pixel(319, 131), 0);
+
+    let (_, buffer) = display.into_raw_mode_and_buffer();
+    assert_eq!(buffer[0], 0x60);
+    assert_eq!(buffer[320 * 132 / 2..], [0xaa; 3]);
+}
+
+#[test]
+fn caller_provided_mono_framebuffer_is_cleared_and_returned() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let buffer: &'static mut [u8] = Box::leak(vec![0xaa; 320 * 132 / 8 + 3].into_boxed_slice());
+    let mut display = new_display(&emulator, DisplayRotation::Rotate0)
+        .into_buffered_mono_mode_with_buffer(buffer)
+        .ok()
+        .unwrap();
+
+    display
+        .draw_iter([Pixel(Point::new(1, 0), BinaryColor::On)])
+        .unwrap();
+    display.flush().unwrap();
+    assert_eq!(emulator.pixel(0, 0), 0);
+    assert_eq!(emulator.pixel(1, 0), 0xf);
+    assert_eq!(emulator.pixel(319, 131), 0);
+
+    let (_, buffer) = display.into_raw_mode_and_buffer();
+    assert_eq!(buffer[0], 0x02);
+    assert_eq!(buffer[320 * 132 / 8..], [0xaa; 3]);
+}
+
+#[test]
+fn short_framebuffer_is_rejected() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let display = new_display(&emulator, DisplayRotation::Rotate0);
+
+    let result = display.into_buffered_graphics_mode_with_buffer([0u8; 320 * 132 / 2 - 1]);
+    let (error, display) = result.err().unwrap();
+    assert!(matches!(
+        error,
+        Error::InvalidParameter("framebuffer length")
+    ));
+
+    let result = display.into_buffered_mono_mode_with_buffer([0u8; 320 * 132 / 8 - 1]);
+    let (error, display) = result.err().unwrap();
+    assert!(matches!(
+        error,
+        Error::InvalidParameter("framebuffer length")
+    ));
+
+    // The display is still usable in the raw mode
+    display.into_buffered_graphics_mode().flush().unwrap();
+}
+
+#[test]
+fn short_shadow_is_rejected() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let display = new_display(&emulator, DisplayRotation::Rotate0).into_buffered_graphics_mode();
+
+    let result = display.enable_diff_flush(vec![0u8; 320 * 132 / 2 - 1]);
+    let (error, mut display) = result.err().unwrap();
+    assert!(matches!(error, Error::InvalidParameter("shadow length")));
+
+    // The diff flush stays disabled
+    assert!(display.disable_diff_flush().is_none());
+    display.flush().unwrap();
+}
+
+/// Row address commands, one per window, received by both controllers
+fn windows(emulator: &Ssd1320z2Emulator<DisplaySize320x132>) -> usize {
+    (0..2)
+        .map(|i| {
+            let controller = emulator.controller(i);
+            let commands = controller.commands();
+            commands
+                .iter()
+                .filter(|command| matches!(command, Command::RowAddress(..)))
+                .count()
+        })
+        .sum()
+}
+
+#[test]
+fn diff_flush_sends_only_changed_bytes() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display =
+        new_display(&emulator, DisplayRotation::Rotate0).into_buffered_graphics_mode();
+    display.draw_iter(pattern_pixels()).unwrap();
+    let mut display = display
+        .enable_diff_flush(vec![0; 320 * 132 / 2])
+        .map_err(|(error, _)| error)
+        .unwrap();
+    // The first flush fills the shadow copy
+    display.flush().unwrap();
+    assert_eq!(data_len(&emulator), 320 * 132 / 2);
+
+    // Unchanged pixels are not sent again
+    let sent = (data_len(&emulator), windows(&emulator));
+    display.draw_iter(pattern_pixels()).unwrap();
+    display.flush().unwrap();
+    assert_eq!((data_len(&emulator), windows(&emulator)), sent);
+
+    // Two changes far apart on one row, one on another one and the same span of three rows
+    let pixels: [(u16, u16); 6] = [(0, 0), (300, 0), (100, 50), (40, 70), (40, 71), (40, 72)];
+    display
+        .draw_iter(pixels.iter().map(|&(x, y)| {
+            Pixel(
+                Point::new(x.into(), y.into()),
+                Gray4::new(pattern(x, y) ^ 0xf),
+            )
+        }))
+        .unwrap();
+    display.flush().unwrap();
+    assert_eq!(data_len(&emulator) - sent.0, 6);
+    assert_eq!(windows(&emulator) - sent.1, 4);
+    for &(x, y) in pixels.iter() {
+        assert_eq!(emulator.pixel(x, y), pattern(x, y) ^ 0xf, "({}, {})", x, y);
+    }
+
+    // Changes close to each other are merged into one span
+    let sent = (data_len(&emulator), windows(&emulator));
+    display
+        .draw_iter(
+            [0, 10]
+                .iter()
+                .map(|&x| Pixel(Point::new(x, 5), Gray4::WHITE)),
+        )
+        .unwrap();
+    display.flush().unwrap();
+    assert_eq!(data_len(&emulator) - sent.0, 6);
+    assert_eq!(windows(&emulator) - sent.1, 1);
+
+    display.disable_diff_flush().unwrap();
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let expected = if pixels.contains(&(x, y)) {
+                pattern(x, y) ^ 0xf
+            } else if (x, y) == (0, 5) || (x, y) == (10, 5) {
+                0xf
+            } else {
+                pattern(x, y)
+            };
+            assert_eq!(emulator.pixel(x, y), expected, "({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn mono_mode_expands_pixels_to_the_levels() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display = new_display(&emulator, DisplayRotation::Rotate0).into_buffered_mono_mode();
+    display
+        .draw_iter([
+            Pixel(Point::new(0, 0), BinaryColor::On),
+            Pixel(Point::new(159, 66), BinaryColor::On),
+            Pixel(Point::new(160, 66), BinaryColor::On),
+            Pixel(Point::new(319, 131), BinaryColor::On),
+        ])
+        .unwrap();
+    display.flush().unwrap();
+    assert_eq!(data_len(&emulator), 320 * 132 / 2);
+
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let on = [(0, 0), (159, 66), (160, 66), (319, 131)].contains(&(x, y));
+            assert_eq!(
+                emulator.pixel(x, y),
+                if on { 0xf } else { 0 },
+                "({}, {})",
+                x,
+                y
+            );
+        }
+    }
+
+    // Only the changed controller gets the drawn area
+    let sent = (
+        emulator.controller(0).data_len(),
+        emulator.controller(1).data_len(),
+    );
+    display
+        .draw_iter([
+            Pixel(Point::new(0, 0), BinaryColor::Off),
+            Pixel(Point::new(3, 2), BinaryColor::On),
+        ])
+        .unwrap();
+    display.flush().unwrap();
+    assert_eq!(emulator.controller(0).data_len(), sent.0 + 6);
+    assert_eq!(emulator.controller(1).data_len(), sent.1);
+    assert_eq!(emulator.pixel(0, 0), 0);
+    assert_eq!(emulator.pixel(3, 2), 0xf);
+
+    // New levels are applied to the whole screen
+    display.set_levels(Gray4::new(2), Gray4::new(9));
+    display.flush().unwrap();
+    assert_eq!(emulator.pixel(3, 2), 9);
+    assert_eq!(emulator.pixel(200, 100), 2);
+    assert_eq!(emulator.pixel(319, 131), 9);
+}
+
+#[test]
+fn mono_mode_rotated_by_90_degrees() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display = new_display(&emulator, DisplayRotation::Rotate90).into_buffered_mono_mode();
+    assert_eq!(display.size(), Size::new(HEIGHT as u32, WIDTH as u32));
+
+    display.clear(BinaryColor::On).unwrap();
+    display
+        .draw_iter([
+            Pixel(Point::new(0, 0), BinaryColor::Off),
+            Pixel(Point::new(131, 319), BinaryColor::Off),
+        ])
+        .unwrap();
+    display.flush().unwrap();
+
+    assert_eq!(emulator.pixel(319, 0), 0);
+    assert_eq!(emulator.pixel(0, 131), 0);
+    assert_eq!(emulator.pixel(1, 131), 0xf);
+    assert_eq!(emulator.pixel(160, 60), 0xf);
+}
+
+#[test]
+fn rows_hidden_by_zoom_are_flushed_after_zooming_out() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display =
+        new_display(&emulator, DisplayRotation::Rotate0).into_buffered_graphics_mode();
+    display.draw_iter(pattern_pixels()).unwrap();
+
+    display.set_zoom(true).unwrap();
+    display.flush().unwrap();
+    assert_eq!(data_len(&emulator), 320 * 66 / 2);
+
+    display.set_zoom(false).unwrap();
+    display.flush().unwrap();
+    assert_eq!(data_len(&emulator), 320 * 132 / 2);
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            assert_eq!(emulator.pixel(x, y), pattern(x, y), "({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn rows_hidden_by_zoom_are_flushed_with_the_diff_flush() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let display = new_display(&emulator, DisplayRotation::Rotate0).into_buffered_graphics_mode();
+    let shadow: &'static mut [u8] = Box::leak(vec![0; 320 * 132 / 2].into_boxed_slice());
+    let mut display = display
+        .enable_diff_flush(shadow)
+        .map_err(|(error, _)| error)
+        .unwrap();
+    display.draw_iter(pattern_pixels()).unwrap();
+
+    display.set_zoom(true).unwrap();
+    display.flush().unwrap();
+    display.set_zoom(false).unwrap();
+    display.flush().unwrap();
+
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            assert_eq!(emulator.pixel(x, y), pattern(x, y), "({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn diff_flush_sends_everything_after_a_rotation() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let display = new_display(&emulator, DisplayRotation::Rotate0).into_buffered_graphics_mode();
+    let mut display = display
+        .enable_diff_flush(vec![0; 320 * 132 / 2])
+        .map_err(|(error, _)| error)
+        .unwrap();
+    display.draw_iter(pattern_pixels()).unwrap();
+    display.flush().unwrap();
+
+    // The framebuffer is unchanged by the redraw, but the display RAM has to be rewritten
+    // in the new orientation
+    display.set_rotation(DisplayRotation::Rotate180).unwrap();
+    display.draw_iter(pattern_pixels()).unwrap();
+    let sent = data_len(&emulator);
+    display.flush().unwrap();
+    assert_eq!(data_len(&emulator) - sent, 320 * 132 / 2);
+
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let shown = emulator.pixel(WIDTH - 1 - x, HEIGHT - 1 - y);
+            assert_eq!(shown, pattern(x, y), "({}, {})", x, y);
+        }
+    }
+
+    // The shadow copy is valid again
+    let sent = data_len(&emulator);
+    display.draw_iter(pattern_pixels()).unwrap();
+    display.flush().unwrap();
+    assert_eq!(data_len(&emulator), sent);
+}
+
+#[test]
+fn flush_ignores_the_raw_draw_area() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display = new_display(&emulator, DisplayRotation::Rotate0);
+    display.set_draw_area((150, 20), (169, 30)).unwrap();
+    display.draw(&[0x11; 7]).unwrap();
+
+    let mut display = display.into_buffered_graphics_mode();
+    display.draw_iter(pattern_pixels()).unwrap();
+    display.flush().unwrap();
+
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            assert_eq!(emulator.pixel(x, y), pattern(x, y), "({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn flush_area_sends_whole_columns_of_the_area() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display =
+        new_display(&emulator, DisplayRotation::Rotate0).into_buffered_graphics_mode();
+    display.flush().unwrap();
+    display.draw_iter(pattern_pixels()).unwrap();
+
+    let sent = data_len(&emulator);
+    display
+        .flush_area(&Rectangle::new(Point::new(157, 3), Size::new(5, 2)))
+        .unwrap();
+    assert_eq!(data_len(&emulator) - sent, 6);
+    for y in 2..6 {
+        for x in 155..163 {
+            let expected = if (156..162).contains(&x) && (3..5).contains(&y) {
+                pattern(x, y)
+            } else {
+                0
+            };
+            assert_eq!(emulator.pixel(x, y), expected, "({}, {})", x, y);
+        }
+    }
+
+    // Areas outside of the display are clipped
+    let sent = data_len(&emulator);
+    display
+        .flush_area(&Rectangle::new(Point::new(-10, 130), Size::new(13, 10)))
+        .unwrap();
+    assert_eq!(data_len(&emulator) - sent, 4);
+    assert_eq!(emulator.pixel(3, 131), pattern(3, 131));
+}
+
+#[test]
+fn unbuffered_fills_pad_odd_edges() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display = new_display(&emulator, DisplayRotation::Rotate0);
+    display.clear(Gray4::new(3)).unwrap();
+    assert!(emulator
+        .render()
+        .pixels()
+        .iter()
+        .all(|&level| level == 0x33));
+
+    display
+        .fill_solid(
+            &Rectangle::new(Point::new(151, 10), Size::new(18, 3)),
+            Gray4::new(9),
+        )
+        .unwrap();
+    for y in 9..14 {
+        for x in 149..171 {
+            let expected = match (x, y) {
+                (151..=168, 10..=12) => 9,
+                // The other pixels of the edge columns are padding
+                (150 | 169, 10..=12) => 0,
+                _ => 3,
+            };
+            assert_eq!(emulator.pixel(x, y), expected, "({}, {})", x, y);
+        }
+    }
+
+    let area = Rectangle::new(Point::new(-3, 120), Size::new(20, 20));
+    display
+        .fill_contiguous(&area, area.points().map(area_color))
+        .unwrap();
+    for y in 119..132 {
+        for x in 0..19 {
+            let expected = match (x, y) {
+                (0..=16, 120..=131) => area_color(Point::new(x.into(), y.into())).luma(),
+                (17, 120..=131) => 0,
+                _ => 3,
+            };
+            assert_eq!(emulator.pixel(x, y), expected, "({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn unbuffered_pixels_of_one_column_are_written_together() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display = new_display(&emulator, DisplayRotation::Rotate0);
+    display.clear(Gray4::new(3)).unwrap();
+
+    display
+        .draw_iter([
+            Pixel(Point::new(10, 5), Gray4::new(7)),
+            Pixel(Point::new(11, 5), Gray4::new(8)),
+            Pixel(Point::new(21, 5), Gray4::new(9)),
+            Pixel(Point::new(400, 5), Gray4::new(9)),
+        ])
+        .unwrap();
+
+    assert_eq!(emulator.pixel(10, 5), 7);
+    assert_eq!(emulator.pixel(11, 5), 8);
+    assert_eq!(emulator.pixel(20, 5), 0);
+    assert_eq!(emulator.pixel(21, 5), 9);
+    assert_eq!(emulator.pixel(22, 5), 3);
+}
+
+#[test]
+fn unbuffered_single_pixels_pad_the_other_pixel_of_their_column() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display = new_display(&emulator, DisplayRotation::Rotate0);
+    display.clear(Gray4::new(3)).unwrap();
+
+    // The line at x = 5 blackens the line at x = 4 drawn before it
+    let line = |x| (10..20).map(move |y| Pixel(Point::new(x, y), Gray4::WHITE));
+    display.draw_iter(line(4)).unwrap();
+    display.draw_iter(line(5)).unwrap();
+    for y in 10..20 {
+        assert_eq!(emulator.pixel(4, y), 0, "(4, {})", y);
+        assert_eq!(emulator.pixel(5, y), 0xf, "(5, {})", y);
+    }
+
+    // Both pixels of a column sent back to back are kept
+    display
+        .draw_iter([
+            Pixel(Point::new(9, 30), Gray4::new(6)),
+            Pixel(Point::new(8, 30), Gray4::new(12)),
+        ])
+        .unwrap();
+    assert_eq!(emulator.pixel(8, 30), 12);
+    assert_eq!(emulator.pixel(9, 30), 6);
+    assert_eq!(emulator.pixel(10, 30), 3);
+}
+
+#[test]
+fn unbuffered_text_matches_the_buffered_mode() {
+    use embedded_graphics::{
+        mono_font::{ascii::FONT_6X10, MonoTextStyle},
+        text::Text,
+    };
+
+    for rotation in [
+        DisplayRotation::Rotate0,
+        DisplayRotation::Rotate90,
+        DisplayRotation::Rotate270,
+    ] {
+        let unbuffered = Ssd1320z2Emulator::new(DisplaySize320x132);
+        let buffered = Ssd1320z2Emulator::new(DisplaySize320x132);
+        let mut unbuffered_display = new_display(&unbuffered, rotation);
+        let mut buffered_display = new_display(&buffered, rotation).into_buffered_graphics_mode();
+
+        // Transparent glyphs are drawn pixel by pixel
+        let style = MonoTextStyle::new(&FONT_6X10, Gray4::new(11));
+        let text = Text::new("SSD1320z2 0123", Point::new(3, 20), style);
+        unbuffered_display.clear(Gray4::BLACK).unwrap();
+        text.draw(&mut unbuffered_display).unwrap();
+        text.draw(&mut buffered_display).unwrap();
+        buffered_display.flush().unwrap();
+
+        assert_eq!(unbuffered.render(), buffered.render(), "{:?}", rotation);
+    }
+}
+
+#[test]
+fn flush_rotated_by_180_degrees() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display =
+        new_display(&emulator, DisplayRotation::Rotate180).into_buffered_graphics_mode();
+
+    display.draw_iter(pattern_pixels()).unwrap();
+    display.flush().unwrap();
+
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let shown = emulator.pixel(WIDTH - 1 - x, HEIGHT - 1 - y);
+            assert_eq!(shown, pattern(x, y), "({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn flush_rotated_by_90_degrees() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display =
+        new_display(&emulator, DisplayRotation::Rotate90).into_buffered_graphics_mode();
+    assert_eq!(display.size(), Size::new(HEIGHT as u32, WIDTH as u32));
+
+    display
+        .draw_iter([
+            Pixel(Point::new(0, 0), Gray4::new(7)),
+            Pixel(Point::new(131, 319), Gray4::new(9)),
+        ])
+        .unwrap();
+    display.flush().unwrap();
+
+    // The portrait image is turned clockwise, its top left corner is the top right one
+    assert_eq!(emulator.pixel(319, 0), 7);
+    assert_eq!(emulator.pixel(0, 131), 9);
+}
+
+#[test]
+fn horizontal_scroll_is_reversed_on_the_remapped_controller() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display = new_display(&emulator, DisplayRotation::Rotate0);
+
+    display
+        .set_horizontal_scroll(
+            ScrollDirection::Right,
+            (170, 10),
+            (249, 20),
+            ScrollInterval::Frames6,
+        )
+        .unwrap();
+    display.set_scroll_active(true).unwrap();
+
+    {
+        // The second controller drives the right half with remapped segments
+        let right = emulator.controller(1);
+        assert!(right.commands().contains(&Command::SegmentRemap(true)));
+        assert!(right.commands().ends_with(&[
+            Command::ScrollActive(false),
+            Command::HorizontalScrollSetup(
+                ScrollDirection::Left,
+                10,
+                ScrollInterval::Frames6,
+                20,
+                5,
+                44
+            ),
+            Command::ScrollActive(true),
+        ]));
+        let left = emulator.controller(0);
+        assert_eq!(left.commands().last(), Some(&Command::ScrollActive(false)));
+    }
+
+    display
+        .set_horizontal_scroll(
+            ScrollDirection::Right,
+            (10, 10),
+            (89, 20),
+            ScrollInterval::Frames6,
+        )
+        .unwrap();
+    let left = emulator.controller(0);
+    assert_eq!(
+        left.commands().last(),
+        Some(&Command::HorizontalScrollSetup(
+            ScrollDirection::Right,
+            10,
+            ScrollInterval::Frames6,
+            20,
+            5,
+            44
+        ))
+    );
+}
+
+#[test]
+fn horizontal_scroll_area_must_not_cross_the_middle() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let mut display = new_display(&emulator, DisplayRotation::Rotate0);
+    let commands = emulator.controller(0).commands().len();
+
+    let result = display.set_horizontal_scroll(
+        ScrollDirection::Left,
+        (100, 0),
+        (219, 9),
+        ScrollInterval::Frames2,
+    );
+
+    assert!(matches!(
+        result,
+        Err(Error::InvalidParameter("scroll area"))
+    ));
+    assert_eq!(emulator.controller(0).commands().len(), commands);
+}
+
+#[test]
+fn start_line_scrolls_both_halves_together() {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x160);
+    let display = Ssd1320z2::new(
+        emulator.interface(),
+        emulator.cs1(),
+        emulator.cs2(),
+        DisplaySize320x160,
+        DisplayRotation::Rotate0,
+    );
+    let mut display = display.init(&Config::default()).unwrap();
+
+    display.set_draw_area((0, 5), (319, 5)).unwrap();
+    display.draw(&[0xff; 160]).unwrap();
+    display.set_start_line(3).unwrap();
+
+    for x in [0, 159, 160, 319] {
+        assert_eq!(emulator.pixel(x, 2), 0xf);
+        assert_eq!(emulator.pixel(x, 5), 0);
+    }
+}
+
+#[test]
+fn single_controller_scans_all_160_rows() {
+    let mut display = Ssd1320::new(Ssd1320Emulator::new(), DisplaySize160x160);
+    display.init(&Config::default()).unwrap();
+
+    display.set_draw_area((0, 159), (1, 159)).unwrap();
+    display.draw(&[0x0c]).unwrap();
+
+    let emulator = display.release();
+    assert!(emulator.commands().contains(&Command::Multiplex(160)));
+    assert_eq!(emulator.ram_pixel(0, 159), 0xc);
+    assert!((0..160).any(|com| emulator.output(0, com) == 0xc));
+}
+
+#[test]
+fn config_multiplex_is_the_number_of_scanned_lines() {
+    let config = Config::default().multiplex(132);
+    let mut display = Ssd1320::new(Ssd1320Emulator::new(), DisplaySize160x160);
+    display.init(&config).unwrap();
+
+    display.set_draw_area((0, 131), (1, 132)).unwrap();
+    display.draw(&[0x0c, 0x0d]).unwrap();
+
+    let emulator = display.release();
+    assert!(emulator
+        .commands()
+        .contains(&Command::multiplex(132).unwrap()));
+    assert!((0..160).any(|com| emulator.output(0, com) == 0xc));
+    assert!((0..160).all(|com| emulator.output(0, com) != 0xd));
+}
+
+#[test]
+fn config_values_out_of_range_fail_init() {
+    for config in [
+        Config::default().multiplex(15),
+        Config::default().multiplex(161),
+        Config::default().display_offset(160),
+        Config::default().clock_div(0, 3),
+        Config::default().precharge_period(0, 1),
+    ] {
+        let mut display = Ssd1320::new(Ssd1320Emulator::new(), DisplaySize160x160);
+        let result = display.init(&config);
+        assert!(
+            matches!(result, Err(Error::InvalidParameter(_))),
+            "{:?}",
+            config
+        );
+    }
+}
+
+#[test]
+fn single_controller_window_wraps_around() {
+    let mut display = Ssd1320::new(Ssd1320Emulator::new(), DisplaySize160x160);
+    display.init(&Config::default()).unwrap();
+
+    display.set_draw_area((4, 2), (7, 3)).unwrap();
+    display.draw(&[0x10, 0x32, 0x54, 0x76, 0x98]).unwrap();
+
+    let emulator = display.release();
+    assert_eq!(emulator.output(7, 2), 0x3);
+    assert_eq!(emulator.output(4, 3), 0x4);
+    assert_eq!(emulator.output(7, 3), 0x7);
+    // The fifth byte is written to the start of the window again
+    assert_eq!(emulator.output(4, 2), 0x8);
+    assert_eq!(emulator.output(5, 2), 0x9);
+}
+
+#[test]
+fn vertical_addressing_fills_columns_first() {
+    let config = Config::default().address_mode(AddrMode::Vertical);
+    let mut display = Ssd1320::new(Ssd1320Emulator::new(), DisplaySize160x160);
+    display.init(&config).unwrap();
+
+    display.set_draw_area((0, 0), (3, 2)).unwrap();
+    display.draw(&[0x11, 0x22, 0x33, 0x44]).unwrap();
+
+    let emulator = display.release();
+    assert_eq!(emulator.ram_pixel(0, 0), 0x1);
+    assert_eq!(emulator.ram_pixel(0, 1), 0x2);
+    assert_eq!(emulator.ram_pixel(0, 2), 0x3);
+    assert_eq!(emulator.ram_pixel(2, 0), 0x4);
+}
+
+#[test]
+fn portrait_addressing_stacks_the_pixels_of_a_byte() {
+    let mut emulator = Ssd1320Emulator::new();
+    Command::PortraitAddressMode(PortraitAddrMode::Portrait)
+        .send(&mut emulator)
+        .unwrap();
+    Command::column_address(1, 1)
+        .unwrap()
+        .send(&mut emulator)
+        .unwrap();
+    Command::row_address(0, 3)
+        .unwrap()
+        .send(&mut emulator)
+        .unwrap();
+    emulator.send_data(U8(&[0x21, 0x43, 0x65])).unwrap();
+
+    assert_eq!(emulator.ram_pixel(2, 0), 0x1);
+    assert_eq!(emulator.ram_pixel(2, 1), 0x2);
+    assert_eq!(emulator.ram_pixel(3, 0), 0x3);
+    assert_eq!(emulator.ram_pixel(3, 1), 0x4);
+    assert_eq!(emulator.ram_pixel(2, 2), 0x5);
+    assert_eq!(emulator.ram_pixel(2, 3), 0x6);
+}
+
+#[test]
+fn remap_com_reverse_and_offset_move_the_output() {
+    let config = Config::default()
+        .segment_remap(true)
+        .com_reverse(true)
+        .display_offset(10);
+    let mut display = Ssd1320::new(Ssd1320Emulator::new(), DisplaySize160x160);
+    display.init(&config).unwrap();
+
+    display.set_draw_area((0, 0), (1, 0)).unwrap();
+    display.draw(&[0x0c]).unwrap();
+
+    let emulator = display.release();
+    // RAM row 0 is scanned last in the reverse direction, shifted up by the offset
+    assert_eq!(emulator.output(159, 149), 0xc);
+    assert_eq!(emulator.output(0, 149), 0);
+}
+
+#[test]
+fn command_lock_ignores_commands() {
+    let mut emulator = Ssd1320Emulator::new();
+    Command::DisplayLock(true).send(&mut emulator).unwrap();
+    Command::DisplayOn(true).send(&mut emulator).unwrap();
+    Command::AllOn(true).send(&mut emulator).unwrap();
+    assert_eq!(emulator.output(0, 0), 0);
+
+    Command::DisplayLock(false).send(&mut emulator).unwrap();
+    Command::DisplayOn(true).send(&mut emulator).unwrap();
+    Command::AllOn(true).send(&mut emulator).unwrap();
+    assert_eq!(emulator.output(0, 0), 0xf);
+}
@@ -0,0 +1,135 @@
+//! Golden image tests of screens drawn through `BufferedSsd1320z2`.
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the current images into `tests/golden`.
+
+use std::{env, path::PathBuf};
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_6X10, MonoTextStyle},
+    pixelcolor::Gray4,
+    prelude::*,
+    primitives::{Circle, Line, PrimitiveStyle, Rectangle},
+    text::Text,
+};
+
+use ssd1320::{
+    config::Config,
+    emulator::{Screenshot, Ssd1320z2Emulator},
+    rotation::DisplayRotation,
+    size::DisplaySize320x132,
+    Ssd1320z2,
+};
+
+/// Draw a status screen using every gray level
+fn draw_screen<D>(target: &mut D)
+where
+    D: DrawTarget<Color = Gray4>,
+    D::Error: core::fmt::Debug,
+{
+    let size = target.bounding_box().size;
+    Rectangle::new(Point::zero(), size)
+        .into_styled(PrimitiveStyle::with_stroke(Gray4::WHITE, 1))
+        .draw(target)
+        .unwrap();
+    for level in 0..16u8 {
+        Rectangle::new(Point::new(4 + level as i32 * 8, 4), Size::new(8, 12))
+            .into_styled(PrimitiveStyle::with_fill(Gray4::new(level)))
+            .draw(target)
+            .unwrap();
+    }
+    Circle::new(Point::new(8, 24), 40)
+        .into_styled(PrimitiveStyle::with_stroke(Gray4::new(10), 3))
+        .draw(target)
+        .unwrap();
+    Line::new(
+        Point::new(0, size.height as i32 - 1),
+        Point::new(size.width as i32 - 1, 0),
+    )
+    .into_styled(PrimitiveStyle::with_stroke(Gray4::new(5), 1))
+    .draw(target)
+    .unwrap();
+    Text::new(
+        "SSD1320z2",
+        Point::new(56, 40),
+        MonoTextStyle::new(&FONT_6X10, Gray4::new(12)),
+    )
+    .draw(target)
+    .unwrap();
+}
+
+fn render_screen(rotation: DisplayRotation) -> Screenshot {
+    let emulator = Ssd1320z2Emulator::new(DisplaySize320x132);
+    let display = Ssd1320z2::new(
+        emulator.interface(),
+        emulator.cs1(),
+        emulator.cs2(),
+        DisplaySize320x132,
+        rotation,
+    );
+    let mut display = display
+        .init(&Config::default())
+        .unwrap()
+        .into_buffered_graphics_mode();
+    draw_screen(&mut display);
+    display.flush().unwrap();
+
+    emulator.render()
+}
+
+/// Compare the screenshot with the golden image of the given name
+fn assert_golden(name: &str, screenshot: &Screenshot) {
+    let golden = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name)
+        .with_extension("pgm");
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        screenshot.save_pgm(&golden).unwrap();
+        return;
+    }
+
+    let expected = Screenshot::load_pgm(&golden).unwrap();
+    if &expected != screenshot {
+        let actual = env::temp_dir().join(name).with_extension("pgm");
+        screenshot.save_pgm(&actual).unwrap();
+        panic!(
+            "{} differs from {}, the rendered image is saved to {}",
+            name,
+            golden.display(),
+            actual.display()
+        );
+    }
+}
+
+#[test]
+fn screen_rotate0() {
+    assert_golden("screen_rotate0", &render_screen(DisplayRotation::Rotate0));
+}
+
+#[test]
+fn screen_rotate90() {
+    assert_golden("screen_rotate90", &render_screen(DisplayRotation::Rotate90));
+}
+
+#[test]
+fn rotate180_is_the_upside_down_image() {
+    let upright = render_screen(DisplayRotation::Rotate0);
+    let flipped = render_screen(DisplayRotation::Rotate180);
+    let (width, height) = (upright.width(), upright.height());
+    for y in 0..height {
+        for x in 0..width {
+            assert_eq!(
+                upright.pixel(x, y),
+                flipped.pixel(width - 1 - x, height - 1 - y)
+            );
+        }
+    }
+}
+
+#[test]
+fn pgm_round_trip() {
+    let screenshot = render_screen(DisplayRotation::Rotate0);
+    let pgm = screenshot.to_pgm();
+    assert!(pgm.starts_with(b"P5\n320 132\n255\n"));
+    assert_eq!(Screenshot::from_pgm(&pgm), Some(screenshot));
+    assert_eq!(Screenshot::from_pgm(b"P5\n2 2\n255\n\0\0\0"), None);
+}